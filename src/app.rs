@@ -1,11 +1,13 @@
+use std::collections::HashMap;
+
 use egui_extras::RetainedImage;
 
 use crate::{
     logger,
     state::{AppState, BorrowedPersistState},
     store::{Image, ImageStore},
-    widgets::{LogWindow, Main, MainView},
-    Channel, FetchImage, SETTINGS_KEY,
+    widgets::{state::ChatViewState, LogWindow, Main, MainView},
+    Channel, ChannelAssets, FetchImage, ModLogEntry, StreamEvent, SETTINGS_KEY,
 };
 
 pub struct App {
@@ -13,6 +15,7 @@ pub struct App {
     pub app: AppState,
     log_window: LogWindow,
     log_recv: flume::Receiver<logger::Record>,
+    title: String,
 }
 
 impl App {
@@ -26,6 +29,24 @@ impl App {
             app,
             log_window: LogWindow::default(),
             log_recv,
+            title: crate::APP_NAME.to_string(),
+        }
+    }
+
+    /// Puts the unread counts in the window title
+    fn try_update_title(&mut self, frame: &mut eframe::Frame) {
+        let (unread, mentions) = self.app.state.chat_view_state.unread();
+        let title = match (unread, mentions) {
+            (0, _) => crate::APP_NAME.to_string(),
+            (unread, 0) => format!("{} ({unread} unread)", crate::APP_NAME),
+            (unread, mentions) => {
+                format!("{} ({unread} unread, {mentions} mentions)", crate::APP_NAME)
+            }
+        };
+
+        if title != self.title {
+            frame.set_window_title(&title);
+            self.title = title;
         }
     }
 
@@ -53,6 +74,12 @@ impl App {
         // self.app.tabs.active_mut().next_line_mode()
     }
 
+    fn jump_to_last_read(&mut self) {
+        if let Some(active) = self.app.state.chat_view_state.active_mut() {
+            active.jump_to_last_read();
+        }
+    }
+
     fn toggle_timestamps(&mut self) {
         if let Some(channel) = self.find_active_channel() {
             channel.show_timestamps = !channel.show_timestamps;
@@ -90,66 +117,121 @@ impl App {
     }
 
     fn try_fetch_chatters(&mut self) {
+        if let Some(helix) = self.app.runtime.helix().cloned() {
+            let token = &self.app.state.config.twitch_oauth_token;
+            let tokens = &self.app.runtime.tokens;
+            let info = (!tokens.is_stale(token)).then(|| tokens.info()).flatten();
+            self.app
+                .runtime
+                .chatters_update
+                .authorize(&helix, info, token);
+        }
+
         for (channel, chatters) in self.app.runtime.chatters_update.poll() {
             if let Some(channel) = self.app.state.chat_view_state.get_mut_by_name(&channel) {
-                *channel.chatters_mut() = chatters;
+                channel.merge_chatters(chatters);
             }
         }
     }
 
     fn try_fetch_badges(&mut self) {
-        const DESIRED_USER_LIST_BADGES: [&str; 8] = [
-            "broadcaster",
-            "vip",
-            "moderator",
-            "staff",
-            "admin",
-            "global_mod",
-            "no_audio",
-            "no_video",
-        ];
-
-        // if we have all of the desired badges already, do nothing
-        if true
-        //  DESIRED_USER_LIST_BADGES
-        // .into_iter()
-        // .fold(true, |ok, key| ok & self.app.state.images.has(key))
-        {
+        if self.app.runtime.global_badges.ready().is_some() {
             return;
         }
 
-        // let badges = match self.app.runtime.global_badges.ready() {
-        //     Some(badges) => badges,
-        //     None => {
-        //         let helix = match self.app.runtime.helix.ready() {
-        //             Some(helix) => helix,
-        //             None => return,
-        //         };
+        let helix = match self.app.runtime.helix() {
+            Some(helix) => helix,
+            None => return,
+        };
 
-        //         let _ = self.app.runtime.helix_ready.send(helix.clone());
-        //         return;
-        //     }
-        // };
+        // this'll fail once the badges have been requested
+        let _ = self.app.runtime.helix_ready.try_send(helix.clone());
+    }
 
-        // for (set_id, (id, url)) in badges.iter().flat_map(|badge| {
-        //     std::iter::repeat(&badge.set_id)
-        //         .zip(badge.versions.iter().map(|v| (&v.id, &v.image_url_4x)))
-        // }) {
-        //     if !DESIRED_USER_LIST_BADGES.contains(&&**set_id) {
-        //         continue;
-        //     }
+    fn try_fetch_channel_assets(&mut self) {
+        for (id, assets) in self.app.runtime.channel_assets.poll() {
+            self.app.state.channel_assets.insert(id, assets);
+        }
 
-        //     let badge = TwitchImage::badge(id, &set_id, url);
-        //     if self.app.state.images.has_id(badge.id()) {
-        //         continue;
-        //     }
+        let helix = match self.app.runtime.helix() {
+            Some(helix) => helix.clone(),
+            None => return,
+        };
 
-        //     if !self.app.state.requested_images.insert(badge.id()) {
-        //         continue;
-        //     }
+        for channel in self.app.state.chat_view_state.channels.iter() {
+            if let Some(channel) = self
+                .app
+                .state
+                .channels
+                .iter()
+                .find(|c| ChatViewState::is_same_channel(&c.login, channel.name()))
+            {
+                self.app.runtime.channel_assets.request(&helix, channel.id);
+            }
+        }
+    }
 
-        //     self.app.runtime.fetch.fetch(badge);
-        // }
+    fn try_poll_streams(&mut self) {
+        if let Some(helix) = self.app.runtime.helix().cloned() {
+            self.app.runtime.streams.set_client(&helix);
+        }
+
+        for (name, stream) in self.app.runtime.streams.poll() {
+            let channel = match self.app.state.chat_view_state.get_mut_by_name(&name) {
+                Some(channel) => channel,
+                None => continue,
+            };
+
+            let events = match channel.has_polled_stream() {
+                true => StreamEvent::diff(channel.stream(), stream.as_ref()),
+                false => StreamEvent::diff_stored(&name, stream.as_ref()),
+            };
+            channel.set_stream(stream);
+
+            let now = time::OffsetDateTime::now_utc().unix_timestamp();
+            for event in events {
+                event.store(&name, now);
+                channel.push_system(format!("{name} {event}"));
+            }
+        }
+    }
+
+    // so we can see what happened while we were away
+    const STREAM_EVENT_HISTORY: i64 = 60 * 60 * 24;
+    const MOD_LOG_HISTORY: i64 = 60 * 60 * 24;
+
+    fn load_stream_events(cvs: &mut ChatViewState, name: &str) {
+        let channel = match cvs.get_mut_by_name(name) {
+            Some(channel) => channel,
+            None => return,
+        };
+
+        let name = name.strip_prefix('#').unwrap_or(name);
+        let since = time::OffsetDateTime::now_utc().unix_timestamp() - Self::STREAM_EVENT_HISTORY;
+        for (at, event) in StreamEvent::since(name, since) {
+            channel.push_system_at(at, format!("{name} {event}"));
+        }
+    }
+
+    fn try_validate_token(&mut self) {
+        let config = &mut self.app.state.config;
+        let tokens = &mut self.app.runtime.tokens;
+
+        match tokens.poll_refresh() {
+            Some(Ok(token)) => {
+                log::info!("refreshed the twitch token");
+                config.twitch_oauth_token = format!("oauth:{}", token.access_token);
+                if let Some(refresh_token) = token.refresh_token {
+                    config.twitch_refresh_token = refresh_token;
+                }
+                tokens.validate(&config.twitch_oauth_token);
+            }
+            Some(Err(err)) => log::warn!("{err}"),
+            None => {}
+        }
+
+        tokens.try_refresh(&config.twitch_client_id, &config.twitch_refresh_token);
+        tokens.poll(&config.twitch_oauth_token);
     }
 
     fn try_fetch_image(&mut self) {
@@ -195,22 +277,288 @@ impl App {
             _ => return,
         };
         self.try_privmsg(&msg);
+        self.try_user_notice(&msg);
+        self.try_clear_chat(&msg);
+        self.try_room_state(&msg);
+        self.try_user_state(&msg);
+        self.try_whisper(&msg);
+        self.try_notice(&msg);
+        self.try_membership(&msg);
+
+        self.app.state.messages.push(msg);
+    }
+
+    fn try_membership(&mut self, msg: &crate::twitch::Message) {
+        let ours = |user| self.app.is_our_name(user);
+        let we_joined = msg.as_join().filter(|join| ours(join.user)).is_some();
+        let we_left = msg.as_part().filter(|part| ours(part.user)).is_some();
+
+        let cvs = &mut self.app.state.chat_view_state;
 
         if let Some(join) = msg.as_join() {
-            if self.app.is_our_name(join.user) {
-                self.app.state.chat_view_state.add_channel(join.channel);
+            if we_joined {
+                cvs.add_channel(join.channel);
+                Self::load_stream_events(cvs, join.channel);
+                if let Some(channel) = cvs.get_mut_by_name(join.channel) {
+                    let now = time::OffsetDateTime::now_utc().unix_timestamp();
+                    channel.load_mod_log(now - Self::MOD_LOG_HISTORY);
+                }
                 self.app.runtime.chatters_update.subscribe(join.channel);
+                self.app.runtime.streams.subscribe(join.channel);
+            }
+            if let Some(channel) = cvs.get_mut_by_name(join.channel) {
+                channel.user_seen(join.user, None);
             }
         }
 
         if let Some(part) = msg.as_part() {
-            if self.app.is_our_name(part.user) {
-                self.app.state.chat_view_state.remove_channel(part.channel);
+            if we_left {
+                cvs.remove_channel(part.channel);
                 self.app.runtime.chatters_update.unsubscribe(part.channel);
+                self.app.runtime.streams.unsubscribe(part.channel);
+            } else if let Some(channel) = cvs.get_mut_by_name(part.channel) {
+                channel.user_left(part.user);
             }
         }
 
-        self.app.state.messages.push(msg);
+        if let Some(names) = msg.as_names() {
+            if let Some(channel) = cvs.get_mut_by_name(names.channel) {
+                for user in names.users() {
+                    channel.user_seen(user, None);
+                }
+            }
+        }
+
+        if let Some(channel) = msg.as_end_of_names() {
+            log::trace!("got the names for: {channel}");
+        }
+    }
+
+    fn try_whisper(&mut self, msg: &crate::twitch::Message) {
+        let whisper = match msg.as_whisper() {
+            Some(whisper) => whisper,
+            _ => return,
+        };
+
+        if self.app.state.ignored_users.contains(whisper.from) {
+            return;
+        }
+
+        self.app
+            .state
+            .chat_view_state
+            .whispers
+            .receive(whisper.from, whisper.data);
+    }
+
+    fn try_notice(&mut self, msg: &crate::twitch::Message) {
+        let notice = match msg.as_notice() {
+            Some(notice) => notice,
+            _ => return,
+        };
+
+        if let Some(active) = self
+            .app
+            .state
+            .chat_view_state
+            .get_mut_by_name(notice.channel)
+        {
+            active.push_system(notice.data);
+        }
+    }
+
+    fn try_poll_moderation(&mut self) {
+        for (channel, action, res) in self.app.runtime.moderation.poll() {
+            let active = match self.app.state.chat_view_state.get_mut_by_name(&channel) {
+                Some(active) => active,
+                None => continue,
+            };
+
+            match &res {
+                Ok(()) => active.push_system(format!("you {action}")),
+                Err(err) => {
+                    log::warn!("cannot moderate {}: {err}", action.login());
+                    active.push_system(format!("cannot moderate {}: {err}", action.login()))
+                }
+            }
+            // the rest are logged when irc tells us about them
+            if res.is_err() || !action.is_reported_by_irc() {
+                active.log_mod(ModLogEntry::Moderation {
+                    action: action.command(),
+                    error: res.err().map(|err| err.to_string()),
+                });
+            }
+        }
+    }
+
+    fn try_poll_whispers(&mut self) {
+        self.app.state.chat_view_state.whispers.poll();
+    }
+
+    fn try_user_state(&mut self, msg: &crate::twitch::Message) {
+        if let Some(identity) = msg.as_identity() {
+            self.app.identity.replace(identity);
+            return;
+        }
+
+        let state = match msg.as_user_state() {
+            Some(state) => state,
+            _ => return,
+        };
+
+        if let Some(active) = self
+            .app
+            .state
+            .chat_view_state
+            .get_mut_by_name(state.channel)
+        {
+            active.set_identity(state.identity);
+        }
+    }
+
+    fn try_room_state(&mut self, msg: &crate::twitch::Message) {
+        let state = match msg.as_room_state() {
+            Some(state) => state,
+            _ => return,
+        };
+
+        if let Some(active) = self
+            .app
+            .state
+            .chat_view_state
+            .get_mut_by_name(state.channel)
+        {
+            for entry in active.room_state(&state) {
+                active.push_system(&entry);
+                active.log_mod(entry);
+            }
+        }
+    }
+
+    fn try_clear_chat(&mut self, msg: &crate::twitch::Message) {
+        if let Some(clear) = msg.as_clear_chat() {
+            let active = match self
+                .app
+                .state
+                .chat_view_state
+                .get_mut_by_name(clear.channel)
+            {
+                Some(active) => active,
+                None => return,
+            };
+
+            active.clear_chat(clear.user);
+            let entry = match (clear.user, clear.duration) {
+                (Some(user), Some(secs)) => ModLogEntry::Timeout {
+                    login: user.to_string(),
+                    secs,
+                },
+                (Some(user), None) => ModLogEntry::Ban {
+                    login: user.to_string(),
+                },
+                (None, ..) => ModLogEntry::ClearChat,
+            };
+            active.push_system(&entry);
+            active.log_mod(entry);
+
+            self.app
+                .state
+                .chat_view_state
+                .clear_mentions(clear.channel, clear.user);
+            return;
+        }
+
+        if let Some(clear) = msg.as_clear_msg() {
+            if let Some(active) = self
+                .app
+                .state
+                .chat_view_state
+                .get_mut_by_name(clear.channel)
+            {
+                active.clear_message(clear.target);
+                active.log_mod(ModLogEntry::DeleteMessage {
+                    login: clear.login.map(ToString::to_string),
+                    message: clear.data.map(ToString::to_string),
+                });
+            }
+            self.app.state.chat_view_state.clear_mention(clear.target);
+        }
+    }
+
+    fn find_channel_assets<'s>(
+        channels: &[Channel],
+        assets: &'s HashMap<u64, ChannelAssets>,
+        channel: &str,
+    ) -> Option<&'s ChannelAssets> {
+        let channel = channels
+            .iter()
+            .find(|c| ChatViewState::is_same_channel(&c.login, channel))?;
+        assets.get(&channel.id)
+    }
+
+    fn is_cheermote(assets: Option<&ChannelAssets>, prefix: &str) -> bool {
+        assets.is_some_and(|assets| assets.find_cheermote(prefix).is_some())
+    }
+
+    fn try_user_notice(&mut self, msg: &crate::twitch::Message) {
+        let notice = match msg.as_user_notice() {
+            Some(notice) => notice,
+            _ => return,
+        };
+
+        let pm = notice.message();
+        if let Some(pm) = &pm {
+            pm.update_emote_map(&mut self.app.state.emote_map);
+        }
+
+        let active = match self
+            .app
+            .state
+            .chat_view_state
+            .get_mut_by_name(notice.channel)
+        {
+            Some(active) => active,
+            None => return,
+        };
+
+        let (highlight, filtered) = pm
+            .as_ref()
+            .map(|pm| {
+                active.user_seen(pm.sender, Some(pm.kind()));
+                let our_name = self.app.identity.as_ref().map(|id| &*id.user_name);
+                (
+                    self.app.state.highlights.find(pm, our_name),
+                    self.app
+                        .state
+                        .filters
+                        .check(pm, &self.app.state.ignored_users),
+                )
+            })
+            .unwrap_or_default();
+
+        let assets = Self::find_channel_assets(
+            &self.app.state.channels,
+            &self.app.state.channel_assets,
+            notice.channel,
+        );
+        let spans = pm.map(|pm| pm.make_spans(|prefix| Self::is_cheermote(assets, prefix)));
+        let id = spans.as_ref().map(|(id, _)| *id);
+        active.push_notice(
+            notice.kind,
+            notice.system_msg,
+            spans,
+            msg.clone(),
+            highlight,
+            filtered,
+        );
+
+        let mention = highlight.is_some_and(|h| h.copy_to_mentions) && filtered.is_none();
+        if let Some(id) = id.filter(|_| mention) {
+            self.app
+                .state
+                .chat_view_state
+                .push_mention(notice.channel, id);
+        }
     }
 
     fn try_privmsg(&mut self, msg: &crate::twitch::Message) {
@@ -221,7 +569,12 @@ impl App {
 
         pm.update_emote_map(&mut self.app.state.emote_map);
 
-        let (id, spans) = pm.make_spans();
+        let assets = Self::find_channel_assets(
+            &self.app.state.channels,
+            &self.app.state.channel_assets,
+            pm.target,
+        );
+        let (id, spans) = pm.make_spans(|prefix| Self::is_cheermote(assets, prefix));
 
         let active = match self.app.state.chat_view_state.get_mut_by_name(pm.target) {
             Some(active) => active,
@@ -235,7 +588,20 @@ impl App {
             }
         };
 
-        active.push_privmsg(id, spans, msg.clone());
+        active.user_seen(pm.sender, Some(pm.kind()));
+
+        let our_name = self.app.identity.as_ref().map(|id| &*id.user_name);
+        let highlight = self.app.state.highlights.find(&pm, our_name);
+        let filtered = self
+            .app
+            .state
+            .filters
+            .check(&pm, &self.app.state.ignored_users);
+        active.push_privmsg(id, spans, msg.clone(), highlight, filtered);
+
+        if highlight.is_some_and(|h| h.copy_to_mentions) && filtered.is_none() {
+            self.app.state.chat_view_state.push_mention(pm.target, id);
+        }
 
         // for (emote, _) in pm.emotes() {
         //     if self.app.state.images.has(emote) {
@@ -335,6 +701,8 @@ impl App {
 
                     NextTab => self.next_tab(),
                     PreviousTab => self.previous_tab(),
+
+                    JumpToLastRead => self.jump_to_last_read(),
                 }
             }
         }
@@ -347,8 +715,13 @@ impl eframe::App for App {
         self.app.state.window_size = frame.info().window_info.size;
 
         self.try_poll_twitch();
+        self.try_validate_token();
         self.try_fetch_badges();
+        self.try_fetch_channel_assets();
         self.try_fetch_chatters();
+        self.try_poll_streams();
+        self.try_poll_whispers();
+        self.try_poll_moderation();
         self.try_fetch_image();
         self.try_update_images();
         self.try_read_message();
@@ -421,7 +794,10 @@ impl eframe::App for App {
         //         });
         //     });
 
-        Main::new(&mut self.app).display(ctx)
+        Main::new(&mut self.app).display(ctx);
+
+        // after the active tab was marked as read
+        self.try_update_title(frame);
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
@@ -429,6 +805,9 @@ impl eframe::App for App {
             env_config: &self.app.state.config,
             key_mapping: &self.app.state.key_mapping,
             channels: &self.app.state.channels,
+            ignored_users: &self.app.state.ignored_users,
+            highlights: &self.app.state.highlights,
+            filters: &self.app.state.filters,
             pixels_per_point: &self.app.state.pixels_per_point,
             tab_bar_position: self.app.state.chat_view_state.tab_bar_position,
             tab_bar_image_size: self.app.state.chat_view_state.image_size,
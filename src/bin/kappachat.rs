@@ -6,7 +6,7 @@ use egui_extras::RetainedImage;
 use kappachat::{
     helix, kappas,
     state::{AppState, PersistState},
-    EnvConfig, SETTINGS_KEY,
+    Endpoints, EnvConfig, Fixtures, SETTINGS_KEY,
 };
 
 const DEFAULT_PIXELS_PER_POINT: f32 = 1.0;
@@ -34,6 +34,9 @@ fn load_settings(state: &mut PersistState, storage: &dyn Storage) {
     merge! {
         pixels_per_point
         channels
+        ignored_users
+        highlights
+        filters
         key_mapping
         tab_bar_image_size
         tab_bar_position
@@ -56,6 +59,7 @@ fn load_settings(state: &mut PersistState, storage: &dyn Storage) {
 
     for extract in [
         (move |e| &mut e.twitch_oauth_token) as Extract,
+        (move |e| &mut e.twitch_refresh_token) as Extract,
         (move |e| &mut e.twitch_name) as Extract,
         (move |e| &mut e.twitch_client_id) as Extract,
         (move |e| &mut e.twitch_client_secret) as Extract,
@@ -91,21 +95,35 @@ fn main() -> anyhow::Result<()> {
 
             cc.egui_ctx.set_pixels_per_point(state.pixels_per_point);
 
+            let endpoints = Endpoints::load_from_env();
+
             let helix = poll_promise::Promise::spawn_thread("helix_initialization", {
                 let config = state.env_config.clone();
+                let endpoints = endpoints.clone();
                 move || {
                     log::trace!("getting helix");
                     let helix = helix::Client::fetch_oauth(
+                        endpoints,
+                        Fixtures::load_from_env(),
                         &config.twitch_client_id,
                         &config.twitch_client_secret,
-                    )
-                    .expect("fetch");
-                    log::trace!("got helix");
+                    );
+                    match &helix {
+                        Ok(..) => log::trace!("got helix"),
+                        Err(err) => log::error!("cannot get helix: {err}"),
+                    }
                     helix
                 }
             });
 
-            let state = AppState::new(cc.egui_ctx.clone(), kappas, state, helix, dark_image_mask);
+            let state = AppState::new(
+                cc.egui_ctx.clone(),
+                kappas,
+                state,
+                endpoints,
+                helix,
+                dark_image_mask,
+            );
             Box::new(kappachat::App::new(cc.egui_ctx.clone(), state, recv))
         }),
     );
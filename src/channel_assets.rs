@@ -0,0 +1,141 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use flume::{Receiver, Sender};
+
+use crate::{helix, store::CacheStore, RequestPaint};
+
+#[derive(Debug, Default)]
+pub struct ChannelAssets {
+    pub emotes: Vec<helix::Emotes>,
+    pub badges: Vec<helix::Badges>,
+    pub cheermotes: Vec<helix::Cheermote>,
+}
+
+impl ChannelAssets {
+    pub fn find_badge(&self, set_id: &str, id: &str) -> Option<&helix::Versions> {
+        helix::Badges::find(&self.badges, set_id, id)
+    }
+
+    pub fn find_cheermote(&self, prefix: &str) -> Option<&helix::Cheermote> {
+        self.cheermotes
+            .iter()
+            .find(|cheermote| cheermote.prefix.eq_ignore_ascii_case(prefix))
+    }
+}
+
+pub struct ChannelAssetsLoader {
+    sub: Sender<(helix::Client, u64)>,
+    // whether all of the assets could be fetched
+    receiver: Receiver<(u64, ChannelAssets, bool)>,
+    requested: HashSet<u64>,
+    // when a failed channel can be requested again
+    retry: HashMap<u64, Instant>,
+}
+
+impl ChannelAssetsLoader {
+    // emotes, badges and cheermotes rarely change, so a day seems fine
+    const TTL: Duration = Duration::from_secs(60 * 60 * 24);
+    const RETRY: Duration = Duration::from_secs(60);
+
+    pub fn create(repaint: impl RequestPaint + 'static) -> Self {
+        let (sub, subscribe) = flume::unbounded();
+        let (sender, receiver) = flume::unbounded();
+
+        std::thread::spawn(move || {
+            for (helix, id) in subscribe {
+                let (assets, complete) = Self::load(&helix, id);
+                if sender.send((id, assets, complete)).is_err() {
+                    break;
+                }
+                repaint.request_repaint();
+            }
+        });
+
+        Self {
+            sub,
+            receiver,
+            requested: HashSet::new(),
+            retry: HashMap::new(),
+        }
+    }
+
+    /// Requests the assets for the channel, this does nothing if they were already requested
+    ///
+    /// Channels that couldn't be fetched are requested again after a while
+    pub fn request(&mut self, helix: &helix::Client, channel_id: u64) -> bool {
+        if self
+            .retry
+            .get(&channel_id)
+            .is_some_and(|&at| Instant::now() < at)
+        {
+            return false;
+        }
+
+        if !self.requested.insert(channel_id) {
+            return false;
+        }
+
+        let _ = self.sub.send((helix.clone(), channel_id));
+        true
+    }
+
+    pub fn poll(&mut self) -> Vec<(u64, ChannelAssets)> {
+        let mut out = vec![];
+        for (id, assets, complete) in self.receiver.try_iter() {
+            if complete {
+                self.retry.remove(&id);
+            } else {
+                self.requested.remove(&id);
+                self.retry.insert(id, Instant::now() + Self::RETRY);
+            }
+            out.push((id, assets));
+        }
+        out
+    }
+
+    fn load(helix: &helix::Client, id: u64) -> (ChannelAssets, bool) {
+        let broadcaster_id = id.to_string();
+
+        let emotes = Self::load_cached(&format!("chat/emotes/{id}"), || {
+            helix.get_channel_emotes(&broadcaster_id)
+        });
+        let badges = Self::load_cached(&format!("chat/badges/{id}"), || {
+            helix.get_channel_badges(&broadcaster_id)
+        });
+        let cheermotes = Self::load_cached(&format!("bits/cheermotes/{id}"), || {
+            helix.get_cheermotes(&broadcaster_id)
+        });
+
+        let complete = emotes.is_some() && badges.is_some() && cheermotes.is_some();
+        let assets = ChannelAssets {
+            emotes: emotes.unwrap_or_default(),
+            badges: badges.unwrap_or_default(),
+            cheermotes: cheermotes.unwrap_or_default(),
+        };
+        (assets, complete)
+    }
+
+    fn load_cached<T>(key: &str, fetch: impl FnOnce() -> helix::Result<Vec<T>>) -> Option<Vec<T>>
+    where
+        T: serde::Serialize + for<'de> serde::Deserialize<'de>,
+    {
+        if let Some(cached) = CacheStore::get(key, Self::TTL) {
+            log::trace!("using cached: {key}");
+            return Some(cached);
+        }
+
+        match fetch() {
+            Ok(data) => {
+                CacheStore::put(key, &data);
+                Some(data)
+            }
+            Err(err) => {
+                log::error!("cannot fetch {key}: {err}");
+                None
+            }
+        }
+    }
+}
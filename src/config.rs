@@ -2,6 +2,9 @@
 pub struct EnvConfig {
     pub twitch_name: String,
     pub twitch_oauth_token: String,
+    /// From logging in with Twitch, used to get a new token once it expires
+    #[serde(default)]
+    pub twitch_refresh_token: String,
 
     pub twitch_client_id: String,
     pub twitch_client_secret: String,
@@ -16,6 +19,7 @@ impl EnvConfig {
         Self {
             twitch_name: get_env("TWITCH_NAME"),
             twitch_oauth_token: get_env("TWITCH_OAUTH_TOKEN"),
+            twitch_refresh_token: get_env("TWITCH_REFRESH_TOKEN"),
 
             twitch_client_id: get_env("TWITCH_CLIENT_ID"),
             twitch_client_secret: get_env("TWITCH_CLIENT_secret"),
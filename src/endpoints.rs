@@ -0,0 +1,54 @@
+/// The services the app talks to
+///
+/// These default to Twitch, but each can be overridden from the environment so
+/// the app can be pointed at local stand-ins
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoints {
+    pub helix: String,
+    pub id: String,
+    pub cdn: String,
+    pub irc: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            helix: "https://api.twitch.tv/helix".into(),
+            id: "https://id.twitch.tv/oauth2".into(),
+            cdn: "https://static-cdn.jtvnw.net".into(),
+            irc: "irc.chat.twitch.tv:6667".into(),
+        }
+    }
+}
+
+impl Endpoints {
+    pub fn load_from_env() -> Self {
+        fn get_env(key: &str, default: String) -> String {
+            std::env::var(key)
+                .ok()
+                .filter(|s| !s.trim().is_empty())
+                .map(|s| s.trim_end_matches('/').to_string())
+                .unwrap_or(default)
+        }
+
+        let default = Self::default();
+        Self {
+            helix: get_env("KAPPACHAT_HELIX_URL", default.helix),
+            id: get_env("KAPPACHAT_ID_URL", default.id),
+            cdn: get_env("KAPPACHAT_CDN_URL", default.cdn),
+            irc: get_env("KAPPACHAT_IRC_ADDRESS", default.irc),
+        }
+    }
+
+    pub fn helix_url(&self, ep: &str) -> String {
+        format!("{}/{ep}", self.helix)
+    }
+
+    pub fn token_url(&self) -> String {
+        format!("{}/token", self.id)
+    }
+
+    pub fn emote_url(&self, id: &str) -> String {
+        format!("{}/emoticons/v2/{id}/default/dark/1.0", self.cdn)
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::BTreeSet;
+
+use regex::{Regex, RegexBuilder};
+
+use crate::twitch::Privmsg;
+
+/// Why a line was kept out of chat
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FilterReason {
+    User,
+    Pattern,
+    Command,
+    Reply,
+}
+
+impl FilterReason {
+    pub const ALL: [Self; 4] = [Self::User, Self::Pattern, Self::Command, Self::Reply];
+
+    pub const fn display(&self) -> &'static str {
+        match self {
+            Self::User => "ignored users",
+            Self::Pattern => "patterns",
+            Self::Command => "commands",
+            Self::Reply => "replies to ignored users",
+        }
+    }
+
+    const fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// How many lines were filtered, by reason
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FilterCounts {
+    counts: [usize; 4],
+}
+
+impl FilterCounts {
+    pub fn add(&mut self, reason: FilterReason) {
+        self.counts[reason.index()] += 1;
+    }
+
+    pub const fn get(&self, reason: FilterReason) -> usize {
+        self.counts[reason.index()]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+/// A regex over the message text
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(from = "String", into = "String")]
+pub struct FilterPattern {
+    pub pattern: String,
+    regex: Option<Result<Regex, String>>,
+}
+
+impl From<String> for FilterPattern {
+    fn from(pattern: String) -> Self {
+        let mut this = Self {
+            pattern,
+            regex: None,
+        };
+        this.compile();
+        this
+    }
+}
+
+impl From<FilterPattern> for String {
+    fn from(pattern: FilterPattern) -> Self {
+        pattern.pattern
+    }
+}
+
+impl FilterPattern {
+    /// Rebuilds the regex, this should be called when the pattern changes
+    pub fn compile(&mut self) {
+        self.regex = (!self.pattern.trim().is_empty()).then(|| {
+            RegexBuilder::new(&self.pattern)
+                .case_insensitive(true)
+                .build()
+                .map_err(|err| err.to_string())
+        });
+    }
+
+    /// The reason the pattern can't be used, if any
+    pub fn error(&self) -> Option<&str> {
+        self.regex.as_ref()?.as_ref().err().map(|s| &**s)
+    }
+
+    fn matches(&self, data: &str) -> bool {
+        matches!(&self.regex, Some(Ok(re)) if re.is_match(data))
+    }
+}
+
+/// Rules for hiding messages, on top of the ignored users
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct MessageFilters {
+    pub patterns: Vec<FilterPattern>,
+    /// Hide messages starting with `!`
+    pub hide_commands: bool,
+    pub hide_replies_to_ignored: bool,
+}
+
+impl MessageFilters {
+    /// Why the message should be filtered, if it should be
+    pub fn check(&self, pm: &Privmsg<'_>, ignored: &BTreeSet<String>) -> Option<FilterReason> {
+        let is_ignored = |login: &str| ignored.contains(&login.to_lowercase());
+
+        if is_ignored(pm.sender) {
+            return Some(FilterReason::User);
+        }
+
+        if self.hide_replies_to_ignored
+            && pm
+                .tags
+                .get("reply-parent-user-login")
+                .is_some_and(is_ignored)
+        {
+            return Some(FilterReason::Reply);
+        }
+
+        if self.hide_commands && pm.data.trim_start().starts_with('!') {
+            return Some(FilterReason::Command);
+        }
+
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(pm.data))
+            .then_some(FilterReason::Pattern)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::Message;
+
+    fn check(filters: &MessageFilters, ignored: &[&str], raw: &str) -> Option<FilterReason> {
+        let ignored = ignored.iter().map(ToString::to_string).collect();
+        let msg = Message::parse(raw).unwrap();
+        filters.check(&msg.as_privmsg().unwrap(), &ignored)
+    }
+
+    #[test]
+    fn check_reasons() {
+        let reply = "@reply-parent-user-login=Troll :bob!bob@bob.tmi.twitch.tv PRIVMSG #c :!hello";
+        let plain = ":bob!bob@bob.tmi.twitch.tv PRIVMSG #c :hello there";
+
+        let mut filters = MessageFilters::default();
+        assert_eq!(check(&filters, &["troll"], reply), None);
+        assert_eq!(check(&filters, &["bob"], plain), Some(FilterReason::User));
+
+        filters.hide_commands = true;
+        assert_eq!(
+            check(&filters, &["troll"], reply),
+            Some(FilterReason::Command)
+        );
+
+        // a reply to an ignored user is checked before commands
+        filters.hide_replies_to_ignored = true;
+        assert_eq!(
+            check(&filters, &["troll"], reply),
+            Some(FilterReason::Reply)
+        );
+
+        filters
+            .patterns
+            .push(FilterPattern::from("THERE$".to_string()));
+        filters.patterns.push(FilterPattern::from("(".to_string()));
+        assert_eq!(check(&filters, &[], plain), Some(FilterReason::Pattern));
+        assert!(filters.patterns[1].error().is_some());
+    }
+}
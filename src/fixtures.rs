@@ -0,0 +1,59 @@
+use std::path::{Path, PathBuf};
+
+/// Whether http responses are recorded to, or replayed from, a fixture directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Fixtures {
+    #[default]
+    Off,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl Fixtures {
+    /// `KAPPACHAT_HTTP_REPLAY` takes priority over `KAPPACHAT_HTTP_RECORD`
+    pub fn load_from_env() -> Self {
+        fn get_env(key: &str) -> Option<PathBuf> {
+            std::env::var_os(key)
+                .filter(|s| !s.is_empty())
+                .map(PathBuf::from)
+        }
+
+        get_env("KAPPACHAT_HTTP_REPLAY")
+            .map(Self::Replay)
+            .or_else(|| get_env("KAPPACHAT_HTTP_RECORD").map(Self::Record))
+            .unwrap_or_default()
+    }
+
+    pub const fn is_replay(&self) -> bool {
+        matches!(self, Self::Replay(..))
+    }
+
+    /// The recorded body for the request, if there is one
+    pub fn read(dir: &Path, ep: &str, query: &[(&str, &str)]) -> Option<String> {
+        let path = Self::path(dir, ep, query);
+        log::trace!("replaying {ep} from: {}", path.display());
+        std::fs::read_to_string(path).ok()
+    }
+
+    pub fn write(dir: &Path, ep: &str, query: &[(&str, &str)], body: &str) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let path = Self::path(dir, ep, query);
+        log::trace!("recording {ep} to: {}", path.display());
+        std::fs::write(path, body)
+    }
+
+    // the query can have hundreds of logins in it, so the file is named by a hash of it.
+    // this uses fnv-1a so the names don't change between builds
+    fn path(dir: &Path, ep: &str, query: &[(&str, &str)]) -> PathBuf {
+        let hash = query
+            .iter()
+            .flat_map(|(k, v)| [k.as_bytes(), b"=", v.as_bytes(), b"&"])
+            .flatten()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, &b| {
+                (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+            });
+
+        let ep = ep.replace('/', "_");
+        dir.join(format!("{ep}-{hash:016x}.json"))
+    }
+}
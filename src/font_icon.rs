@@ -6,6 +6,16 @@ pub const HELP: &str = "❔";
 pub const TIME: &str = "⏰";
 pub const AUTOJOIN: &str = "🔜";
 pub const USER_LIST: &str = "🚮";
+pub const LIVE: &str = "●";
+pub const WHISPER: &str = "✉";
+pub const MENTIONS: &str = "🔔";
+pub const MOD_LOG: &str = "📜";
+pub const FILTERED: &str = "🚫";
+pub const SLOW_MODE: &str = "⏳";
+pub const FOLLOWERS_ONLY: &str = "♥";
+pub const SUBS_ONLY: &str = "★";
+pub const EMOTE_ONLY: &str = "☺";
+pub const R9K: &str = "🔂";
 
 pub const UP_TRIANGLE: &str = "⏶";
 pub const DOWN_TRIANGLE: &str = "⏷";
@@ -1,7 +1,14 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use parking_lot::{Mutex, RwLock};
 use uuid::Uuid;
 
+use crate::{Endpoints, Fixtures};
+
 pub struct Channel {
     pub id: u64,
     pub login: String,
@@ -16,7 +23,64 @@ pub struct Channel {
 #[derive(Debug, Default)]
 pub struct Chatters {
     pub count: usize,
-    pub chatters: Vec<(Kind, String)>,
+    // sorted by role then name, for the user list
+    chatters: BTreeSet<(Kind, String)>,
+    // the role of each chatter, so lookups don't walk the list
+    kinds: HashMap<String, Kind>,
+}
+
+impl Chatters {
+    /// Adds the chatter, replacing their role if they're already in the list
+    pub fn insert(&mut self, kind: Kind, name: impl ToString) {
+        let name = name.to_string();
+        if let Some(old) = self.kinds.insert(name.clone(), kind) {
+            self.chatters.remove(&(old, name.clone()));
+        }
+        self.chatters.insert((kind, name));
+        self.count = self.chatters.len();
+    }
+
+    pub fn get(&self, name: &str) -> Option<Kind> {
+        self.kinds.get(name).copied()
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Kind> {
+        let (name, kind) = self.kinds.remove_entry(name)?;
+        self.chatters.remove(&(kind, name));
+        self.count = self.chatters.len();
+        Some(kind)
+    }
+
+    /// The chatters, sorted by role then name
+    pub fn iter(&self) -> impl Iterator<Item = (Kind, &str)> + '_ {
+        self.chatters
+            .iter()
+            .map(|(kind, name)| (*kind, name.as_str()))
+    }
+}
+
+impl IntoIterator for Chatters {
+    type Item = (Kind, String);
+    type IntoIter = std::collections::btree_set::IntoIter<(Kind, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chatters.into_iter()
+    }
+}
+
+impl FromIterator<(Kind, String)> for Chatters {
+    fn from_iter<T: IntoIterator<Item = (Kind, String)>>(iter: T) -> Self {
+        let kinds: HashMap<_, _> = iter.into_iter().map(|(kind, name)| (name, kind)).collect();
+        let chatters: BTreeSet<_> = kinds
+            .iter()
+            .map(|(name, kind)| (*kind, name.clone()))
+            .collect();
+        Self {
+            count: chatters.len(),
+            chatters,
+            kinds,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
@@ -57,7 +121,142 @@ impl Kind {
     }
 }
 
-#[derive(Clone, Debug, Default, ::serde::Deserialize)]
+#[derive(Debug)]
+pub enum Error {
+    /// The credentials were missing or rejected (401, 403)
+    Auth(String),
+    /// Twitch kept rate limiting us, even after waiting (429)
+    RateLimited(String),
+    /// The resource doesn't exist (404)
+    NotFound(String),
+    /// Twitch had a problem, even after retrying (5xx)
+    Server(u16, String),
+    /// Any other unsuccessful status
+    Status(u16, String),
+    /// The request couldn't be sent, or the response couldn't be read
+    Transport(String),
+    /// The response wasn't what we expected
+    Decode(String),
+    /// The request would reach Twitch while replaying fixtures
+    Replay(String),
+}
+
+impl Error {
+    fn from_status(code: u16, resp: ureq::Response) -> Self {
+        #[derive(serde::Deserialize)]
+        struct Message {
+            message: String,
+        }
+
+        let body = resp.into_string().unwrap_or_default();
+        let message = serde_json::from_str::<Message>(&body)
+            .map(|msg| msg.message)
+            .unwrap_or(body);
+
+        match code {
+            401 | 403 => Self::Auth(message),
+            404 => Self::NotFound(message),
+            429 => Self::RateLimited(message),
+            500..=599 => Self::Server(code, message),
+            _ => Self::Status(code, message),
+        }
+    }
+
+    pub const fn is_auth(&self) -> bool {
+        matches!(self, Self::Auth(..))
+    }
+
+    pub const fn is_rate_limited(&self) -> bool {
+        matches!(self, Self::RateLimited(..))
+    }
+
+    pub const fn is_not_found(&self) -> bool {
+        matches!(self, Self::NotFound(..))
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Auth(msg) => write!(f, "unauthorized: {msg}"),
+            Self::RateLimited(msg) => write!(f, "rate limited: {msg}"),
+            Self::NotFound(msg) => write!(f, "not found: {msg}"),
+            Self::Server(code, msg) => write!(f, "server error ({code}): {msg}"),
+            Self::Status(code, msg) => write!(f, "unexpected status ({code}): {msg}"),
+            Self::Transport(msg) => write!(f, "transport error: {msg}"),
+            Self::Decode(msg) => write!(f, "invalid response: {msg}"),
+            Self::Replay(msg) => write!(f, "cannot send while replaying: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ureq::Error> for Error {
+    fn from(err: ureq::Error) -> Self {
+        match err {
+            ureq::Error::Status(code, resp) => Self::from_status(code, resp),
+            ureq::Error::Transport(err) => Self::Transport(err.to_string()),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Decode(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Decode(err.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The rate limit budget Twitch reported on the last response
+#[derive(Debug, Default)]
+struct RateLimit {
+    remaining: Option<u64>,
+    reset: Option<u64>,
+}
+
+impl RateLimit {
+    // don't wait forever if the clocks disagree
+    const MAX_WAIT: Duration = Duration::from_secs(60);
+
+    fn update(&mut self, resp: &ureq::Response) {
+        let get = |key| resp.header(key).and_then(|s| s.parse().ok());
+        if let Some(remaining) = get("ratelimit-remaining") {
+            self.remaining.replace(remaining);
+        }
+        if let Some(reset) = get("ratelimit-reset") {
+            self.reset.replace(reset);
+        }
+    }
+
+    /// How long until the bucket refills
+    fn until_reset(&self) -> Option<Duration> {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_secs();
+
+        let reset = self.reset?.checked_sub(now)?;
+        Some(Duration::from_secs(reset + 1).min(Self::MAX_WAIT))
+    }
+
+    /// How long to wait before the next request
+    fn delay(&self) -> Option<Duration> {
+        if self.remaining? > 0 {
+            return None;
+        }
+        self.until_reset()
+    }
+}
+
+#[derive(Clone, Debug, ::serde::Deserialize)]
 struct OAuth {
     access_token: String,
     refresh_token: Option<String>,
@@ -67,18 +266,89 @@ struct OAuth {
     #[serde(default)]
     client_id: String,
 
+    #[serde(skip)]
+    client_secret: String,
+
     #[serde(skip)]
     bearer_token: String,
+
+    #[serde(skip, default = "Instant::now")]
+    created_at: Instant,
 }
 
 impl OAuth {
+    // refresh the token this long before it actually expires
+    const REFRESH_MARGIN: Duration = Duration::from_secs(5 * 60);
+
+    fn expires_soon(&self) -> bool {
+        self.created_at.elapsed() + Self::REFRESH_MARGIN >= Duration::from_secs(self.expires_in)
+    }
+
+    /// Whether we can get a new token, external tokens have neither a secret nor a refresh token
+    fn can_refresh(&self) -> bool {
+        self.refresh_token.is_some() || !self.client_secret.is_empty()
+    }
+
+    fn refresh(&self, agent: ureq::Agent, endpoints: &Endpoints) -> Result<Self> {
+        let refresh_token = match &self.refresh_token {
+            Some(refresh_token) => refresh_token,
+            // app tokens don't have a refresh token, so just get a new one
+            None => return Self::create(agent, endpoints, &self.client_id, &self.client_secret),
+        };
+
+        let req = [
+            ("client_id", &*self.client_id),
+            ("client_secret", &*self.client_secret),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ]
+        .into_iter()
+        .fold(agent.post(&endpoints.token_url()), |req, (k, v)| {
+            req.query(k, v)
+        });
+
+        let this: Self = req.call()?.into_json()?;
+        Ok(self.with_credentials(this))
+    }
+
+    fn with_credentials(&self, this: Self) -> Self {
+        Self {
+            client_id: self.client_id.clone(),
+            client_secret: self.client_secret.clone(),
+            bearer_token: format!("Bearer {}", this.access_token),
+            ..this
+        }
+    }
+
+    /// A token that we don't manage, so it never expires here
+    ///
+    /// This is used for user tokens, and with an empty token for when the responses are replayed
+    fn external(client_id: &str, token: &str) -> Self {
+        let token = token.strip_prefix("oauth:").unwrap_or(token);
+        Self {
+            access_token: token.to_string(),
+            refresh_token: None,
+            expires_in: u64::MAX,
+            token_type: "bearer".into(),
+            client_id: client_id.to_string(),
+            client_secret: String::new(),
+            bearer_token: format!("Bearer {token}"),
+            created_at: Instant::now(),
+        }
+    }
+
     pub fn create(
         agent: ureq::Agent,
+        endpoints: &Endpoints,
         client_id: &str,
         client_secret: &str,
-    ) -> anyhow::Result<Self> {
-        anyhow::ensure!(!client_id.is_empty(), "twitch client id was empty");
-        anyhow::ensure!(!client_secret.is_empty(), "twitch client secret was empty");
+    ) -> Result<Self> {
+        if client_id.is_empty() {
+            return Err(Error::Auth("twitch client id was empty".into()));
+        }
+        if client_secret.is_empty() {
+            return Err(Error::Auth("twitch client secret was empty".into()));
+        }
 
         let req = [
             ("client_id", client_id),
@@ -86,17 +356,17 @@ impl OAuth {
             ("grant_type", "client_credentials"),
         ]
         .into_iter()
-        .fold(
-            agent.post("https://id.twitch.tv/oauth2/token"),
-            |req, (k, v)| req.query(k, v),
-        );
+        .fold(agent.post(&endpoints.token_url()), |req, (k, v)| {
+            req.query(k, v)
+        });
 
-        let resp = req.call()?.into_json();
-        Ok(resp.map(|this: Self| Self {
+        let this: Self = req.call()?.into_json()?;
+        Ok(Self {
             client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
             bearer_token: format!("Bearer {}", this.access_token),
             ..this
-        })?)
+        })
     }
 }
 
@@ -117,140 +387,446 @@ pub enum IdOrLogin<'a> {
 
 #[derive(Clone)]
 pub struct Client {
-    oauth: Arc<OAuth>,
+    oauth: Arc<RwLock<OAuth>>,
+    limits: Arc<Mutex<RateLimit>>,
+    endpoints: Arc<Endpoints>,
+    fixtures: Arc<Fixtures>,
     agent: ureq::Agent,
 }
 
 impl Client {
-    pub fn fetch_oauth(client_id: &str, client_secret: &str) -> anyhow::Result<Self> {
+    /// Gets an app token and creates the client
+    ///
+    /// When replaying fixtures this doesn't touch the network, so the credentials can be empty
+    pub fn fetch_oauth(
+        endpoints: Endpoints,
+        fixtures: Fixtures,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<Self> {
         let agent = ureq::agent();
-        let oauth = OAuth::create(agent.clone(), client_id, client_secret).map(Arc::new)?;
-        Ok(Self { oauth, agent })
+        let oauth = if fixtures.is_replay() {
+            OAuth::external(client_id, "")
+        } else {
+            OAuth::create(agent.clone(), &endpoints, client_id, client_secret)?
+        };
+
+        Ok(Self {
+            oauth: Arc::new(RwLock::new(oauth)),
+            limits: Arc::default(),
+            endpoints: Arc::new(endpoints),
+            fixtures: Arc::new(fixtures),
+            agent,
+        })
+    }
+
+    /// A client that uses the user's token, rather than the app token
+    ///
+    /// The `client_id` must be the one the token was issued for
+    pub fn for_user(&self, client_id: &str, token: &str) -> Self {
+        Self {
+            oauth: Arc::new(RwLock::new(OAuth::external(client_id, token))),
+            limits: Arc::default(),
+            endpoints: self.endpoints.clone(),
+            fixtures: self.fixtures.clone(),
+            agent: self.agent.clone(),
+        }
     }
 
-    pub fn get_chatters_for(channel: &str) -> anyhow::Result<Chatters> {
-        let resp = ureq::get(&format!(
-            "https://tmi.twitch.tv/group/user/{channel}/chatters"
-        ))
-        .call()?
-        .into_string()?;
+    pub fn endpoints(&self) -> &Endpoints {
+        &self.endpoints
+    }
+
+    fn refresh_token(&self) -> Result<()> {
+        log::debug!("refreshing the helix token");
+        let oauth = self
+            .oauth
+            .read()
+            .refresh(self.agent.clone(), &self.endpoints)?;
+        *self.oauth.write() = oauth;
+        Ok(())
+    }
+
+    /// Gets the users connected to the broadcaster's chat
+    ///
+    /// This needs a user token with `moderator:read:chatters` for a moderator of the channel.
+    /// Helix doesn't say what role a chatter has, so everyone is a viewer
+    pub fn get_chatters(&self, broadcaster_id: &str, moderator_id: &str) -> Result<Chatters> {
+        #[derive(serde::Deserialize)]
+        struct Chatter {
+            user_login: String,
+        }
 
-        Self::chatters_json(&resp)
+        let chatters: Vec<Chatter> = self.get_paginated(
+            "chat/chatters",
+            &[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", moderator_id),
+                ("first", "1000"),
+            ],
+            &[],
+            usize::MAX,
+        )?;
+
+        Ok(chatters
+            .into_iter()
+            .map(|chatter| (Kind::Viewer, chatter.user_login))
+            .collect())
     }
 
     pub fn get_users<'a>(
         &self,
         logins: impl IntoIterator<Item = IdOrLogin<'a>>,
-    ) -> anyhow::Result<Vec<User>> {
-        // TODO split on 100 query-pair boundaries
-        self.get_response(
-            "users",
-            logins.into_iter().map(|input| match input {
+    ) -> Result<Vec<User>> {
+        let list = logins
+            .into_iter()
+            .map(|input| match input {
                 IdOrLogin::Id(id) => ("id", id),
                 IdOrLogin::Login(login) => ("login", login.strip_prefix('#').unwrap_or(login)),
-            }),
+            })
+            .collect::<Vec<_>>();
+
+        self.get_paginated("users", &[], &list, usize::MAX)
+    }
+
+    pub fn get_emotes(&self) -> Result<Vec<Emotes>> {
+        self.get_paginated("chat/emotes/global", &[], &[], usize::MAX)
+    }
+
+    pub fn get_badges(&self) -> Result<Vec<Badges>> {
+        self.get_paginated("chat/badges/global", &[], &[], usize::MAX)
+    }
+
+    pub fn get_channel_emotes(&self, broadcaster_id: &str) -> Result<Vec<Emotes>> {
+        self.get_paginated(
+            "chat/emotes",
+            &[("broadcaster_id", broadcaster_id)],
+            &[],
+            usize::MAX,
         )
     }
 
-    pub fn get_emotes(&self) -> anyhow::Result<Vec<Emotes>> {
-        self.get_response("chat/emotes/global", [])
+    pub fn get_channel_badges(&self, broadcaster_id: &str) -> Result<Vec<Badges>> {
+        self.get_paginated(
+            "chat/badges",
+            &[("broadcaster_id", broadcaster_id)],
+            &[],
+            usize::MAX,
+        )
+    }
+
+    /// The global cheermotes, and the ones for the broadcaster
+    pub fn get_cheermotes(&self, broadcaster_id: &str) -> Result<Vec<Cheermote>> {
+        self.get_paginated(
+            "bits/cheermotes",
+            &[("broadcaster_id", broadcaster_id)],
+            &[],
+            usize::MAX,
+        )
     }
 
-    pub fn get_badges(&self) -> anyhow::Result<Vec<Badges>> {
-        self.get_response("chat/badges/global", [])
+    /// Whispers the user
+    ///
+    /// This needs a user token with `user:manage:whispers` for the sender
+    pub fn send_whisper(&self, from_user_id: &str, to_user_id: &str, message: &str) -> Result<()> {
+        self.request(
+            "POST",
+            "whispers",
+            &[("from_user_id", from_user_id), ("to_user_id", to_user_id)],
+            Some(&serde_json::json!({ "message": message })),
+        )?;
+        Ok(())
+    }
+
+    /// Bans the user, or times them out for `duration` seconds
+    ///
+    /// This needs a user token with `moderator:manage:banned_users` for a moderator of the channel
+    pub fn ban_user(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        user_id: &str,
+        duration: Option<u64>,
+    ) -> Result<()> {
+        let mut data = serde_json::json!({ "user_id": user_id });
+        if let Some(duration) = duration {
+            data["duration"] = duration.into();
+        }
+
+        self.request(
+            "POST",
+            "moderation/bans",
+            &[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", moderator_id),
+            ],
+            Some(&serde_json::json!({ "data": data })),
+        )?;
+        Ok(())
+    }
+
+    /// Lifts a ban or timeout
+    ///
+    /// This needs a user token with `moderator:manage:banned_users` for a moderator of the channel
+    pub fn unban_user(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        user_id: &str,
+    ) -> Result<()> {
+        self.request(
+            "DELETE",
+            "moderation/bans",
+            &[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", moderator_id),
+                ("user_id", user_id),
+            ],
+            None,
+        )?;
+        Ok(())
+    }
+
+    /// Deletes a single chat message
+    ///
+    /// This needs a user token with `moderator:manage:chat_messages` for a moderator of the channel
+    pub fn delete_chat_message(
+        &self,
+        broadcaster_id: &str,
+        moderator_id: &str,
+        message_id: &str,
+    ) -> Result<()> {
+        self.request(
+            "DELETE",
+            "moderation/chat",
+            &[
+                ("broadcaster_id", broadcaster_id),
+                ("moderator_id", moderator_id),
+                ("message_id", message_id),
+            ],
+            None,
+        )?;
+        Ok(())
     }
 
-    pub fn get_stream_for(&self, channel: &str) -> anyhow::Result<Stream> {
+    /// Gets the stream for the channel, or `None` if they're offline
+    pub fn get_stream_for(&self, channel: &str) -> Result<Option<Stream>> {
         let mut streams =
-            self.get_response("streams", [("user_login", channel), ("first", "1")])?;
-        Ok(streams.remove(0))
+            self.get_paginated("streams", &[("first", "1")], &[("user_login", channel)], 1)?;
+        Ok(streams.pop())
+    }
+
+    /// Gets the streams for the channels that are live
+    pub fn get_streams<'a>(
+        &self,
+        channels: impl IntoIterator<Item = &'a str>,
+    ) -> Result<Vec<Stream>> {
+        let list = channels
+            .into_iter()
+            .map(|channel| ("user_login", channel.strip_prefix('#').unwrap_or(channel)))
+            .collect::<Vec<_>>();
+
+        if list.is_empty() {
+            return Ok(vec![]);
+        }
+
+        self.get_paginated("streams", &[("first", "100")], &list, usize::MAX)
+    }
+
+    /// Twitch allows at most this many of a repeated query parameter per request
+    const MAX_PER_REQUEST: usize = 100;
+
+    /// Requests `ep` with `query` and the `list` of repeated query pairs.
+    ///
+    /// The `list` is split into chunks of `MAX_PER_REQUEST`, and each chunk follows
+    /// the pagination cursor until the end is reached or `limit` items were collected.
+    fn get_paginated<T>(
+        &self,
+        ep: &str,
+        query: &[(&str, &str)],
+        list: &[(&str, &str)],
+        limit: usize,
+    ) -> Result<Vec<T>>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        // an empty list is still a single request
+        let chunks = if list.is_empty() {
+            vec![&[][..]]
+        } else {
+            list.chunks(Self::MAX_PER_REQUEST).collect()
+        };
+
+        let mut out = vec![];
+        for chunk in chunks {
+            let mut cursor = None::<String>;
+            loop {
+                let after = cursor.as_deref().map(|cursor| ("after", cursor));
+                let (data, next) =
+                    self.get_response(ep, query.iter().chain(chunk).copied().chain(after))?;
+
+                out.extend(data);
+                if out.len() >= limit {
+                    out.truncate(limit);
+                    return Ok(out);
+                }
+
+                match next.filter(|next| !next.is_empty()) {
+                    Some(next) => cursor.replace(next),
+                    None => break,
+                };
+            }
+        }
+
+        Ok(out)
     }
 
     fn get_response<'k, 'v, T>(
         &self,
         ep: &str,
         query: impl IntoIterator<Item = (&'k str, &'v str)>,
-    ) -> anyhow::Result<Vec<T>>
+    ) -> Result<(Vec<T>, Option<String>)>
     where
         T: for<'de> serde::Deserialize<'de>,
     {
+        #[derive(Default, serde::Deserialize)]
+        struct Pagination {
+            cursor: Option<String>,
+        }
+
         #[derive(serde::Deserialize)]
         struct Resp<T> {
             data: Vec<T>,
+            #[serde(default)]
+            pagination: Pagination,
         }
 
-        let req = self.agent.get(&format!("https://api.twitch.tv/helix/{ep}"));
-        let req = query.into_iter().fold(req, |req, (k, v)| req.query(k, v));
+        let query = query.into_iter().collect::<Vec<_>>();
 
-        let req = [
-            ("client-id", &self.oauth.client_id), //
-            ("authorization", &self.oauth.bearer_token),
-        ]
-        .into_iter()
-        .fold(req, |req, (k, v)| req.set(k, v));
-
-        #[cfg(feature = "save_http_json")]
-        let resp: Resp<T> = {
-            let s = req.call()?.into_string()?;
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64;
-            let ep = ep.replace('/', "_");
-            let path = format!("{ep}_{now}.json");
-            eprintln!("saving json to: {path}");
-            std::fs::write(path, &s).unwrap();
-            serde_json::from_str(&s)?
+        let body = match &*self.fixtures {
+            Fixtures::Replay(dir) => Fixtures::read(dir, ep, &query)
+                .ok_or_else(|| Error::NotFound(format!("no recorded response for: {ep}")))?,
+            fixtures => {
+                let body = self.send(ep, &query)?.into_string()?;
+                if let Fixtures::Record(dir) = fixtures {
+                    if let Err(err) = Fixtures::write(dir, ep, &query, &body) {
+                        log::warn!("cannot record response for: {ep} because: {err}")
+                    }
+                }
+                body
+            }
         };
 
-        #[cfg(not(feature = "save_http_json"))]
-        let resp: Resp<T> = req.call()?.into_json()?;
+        let resp: Resp<T> = serde_json::from_str(&body)?;
+        Ok((resp.data, resp.pagination.cursor))
+    }
+
+    const MAX_RETRIES: u32 = 3;
 
-        Ok(resp.data)
+    fn send(&self, ep: &str, query: &[(&str, &str)]) -> Result<ureq::Response> {
+        self.request("GET", ep, query, None)
     }
 
-    pub fn chatters_json(json: &str) -> anyhow::Result<Chatters> {
-        #[derive(serde::Deserialize)]
-        struct Chatters {
-            admins: Vec<String>,
-            broadcaster: Vec<String>,
-            global_mods: Vec<String>,
-            moderators: Vec<String>,
-            staff: Vec<String>,
-            viewers: Vec<String>,
-            vips: Vec<String>,
+    /// Sends the request, waiting on the rate limit and retrying on transient failures
+    ///
+    /// A 401 refreshes the token and retries once, a 429 or 5xx retries with a jittered backoff.
+    /// User tokens can't be refreshed here, so their 401 is returned as is
+    fn request(
+        &self,
+        method: &str,
+        ep: &str,
+        query: &[(&str, &str)],
+        body: Option<&serde_json::Value>,
+    ) -> Result<ureq::Response> {
+        // only reads are recorded, so posts and deletes would go out for real
+        if self.fixtures.is_replay() {
+            return Err(Error::Replay(format!("{method} {ep}")));
         }
 
-        #[derive(serde::Deserialize)]
-        struct Resp {
-            #[serde(rename = "chatter_count")]
-            count: usize,
-            chatters: Chatters,
+        let can_refresh = self.oauth.read().can_refresh();
+        if can_refresh && self.oauth.read().expires_soon() {
+            self.refresh_token()?;
         }
 
-        let resp: Resp = serde_json::from_str(json)?;
-
-        use self::Kind::*;
-        use std::iter::repeat;
-
-        let (count, chatters) = (resp.count, resp.chatters);
-        let head = chatters.admins.into_iter().zip(repeat(Admin));
-        let chatters = head
-            .chain(chatters.broadcaster.into_iter().zip(repeat(Broadcaster)))
-            .chain(chatters.global_mods.into_iter().zip(repeat(GlobalMod)))
-            .chain(chatters.moderators.into_iter().zip(repeat(Moderator)))
-            .chain(chatters.staff.into_iter().zip(repeat(Staff)))
-            .chain(chatters.vips.into_iter().zip(repeat(Vip)))
-            .chain(chatters.viewers.into_iter().zip(repeat(Viewer)))
-            .map(|(name, kind)| (kind, name))
-            .collect();
+        let mut refreshed = false;
+        let mut attempt = 0;
+
+        loop {
+            let delay = self.limits.lock().delay();
+            if let Some(delay) = delay {
+                log::warn!("helix rate limit exhausted, waiting {delay:?}");
+                std::thread::sleep(delay);
+            }
+
+            let err = match self.call(method, ep, query, body) {
+                Ok(resp) => {
+                    self.limits.lock().update(&resp);
+                    return Ok(resp);
+                }
+                Err(err) => *err,
+            };
+
+            match err {
+                ureq::Error::Status(401, _) if can_refresh && !refreshed => {
+                    log::warn!("helix rejected the token for: {ep}");
+                    refreshed = true;
+                    self.refresh_token()?;
+                }
+
+                ureq::Error::Status(code @ (429 | 500..=599), resp)
+                    if attempt < Self::MAX_RETRIES =>
+                {
+                    let mut limits = self.limits.lock();
+                    limits.update(&resp);
+
+                    let backoff = match code {
+                        429 => limits.until_reset(),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| Duration::from_millis(500 * 2_u64.pow(attempt)));
+                    drop(limits);
+
+                    let delay = backoff + Duration::from_millis(fastrand::u64(0..250));
+                    log::warn!("helix returned {code} for: {ep}, retrying in {delay:?}");
+                    std::thread::sleep(delay);
+                    attempt += 1;
+                }
+
+                err => return Err(err.into()),
+            }
+        }
+    }
+
+    fn call(
+        &self,
+        method: &str,
+        ep: &str,
+        query: &[(&str, &str)],
+        body: Option<&serde_json::Value>,
+    ) -> std::result::Result<ureq::Response, Box<ureq::Error>> {
+        let req = self.agent.request(method, &self.endpoints.helix_url(ep));
+        let req = query.iter().fold(req, |req, (k, v)| req.query(k, v));
+
+        let oauth = self.oauth.read();
+        let req = [
+            ("client-id", &oauth.client_id), //
+            ("authorization", &oauth.bearer_token),
+        ]
+        .into_iter()
+        .fold(req, |req, (k, v)| req.set(k, v));
+        drop(oauth);
 
-        Ok(self::Chatters { count, chatters })
+        match body {
+            Some(body) => req.send_json(body),
+            None => req.call(),
+        }
+        .map_err(Box::new)
     }
 }
 
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Stream {
+    pub id: String,
     pub user_id: String,
     pub user_login: String,
     pub user_name: String,
@@ -258,11 +834,22 @@ pub struct Stream {
     #[serde(rename = "type")]
     pub ty: String,
     pub title: String,
+    #[serde(default)]
+    pub viewer_count: u64,
     #[serde(with = "time::serde::rfc3339")]
     pub started_at: time::OffsetDateTime,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+impl Stream {
+    /// How long the stream has been live, in seconds
+    pub fn uptime(&self) -> u64 {
+        (time::OffsetDateTime::now_utc() - self.started_at)
+            .whole_seconds()
+            .max(0) as _
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Emotes {
     pub format: Vec<String>,
     pub id: String,
@@ -270,18 +857,190 @@ pub struct Emotes {
     pub name: String,
     pub scale: Vec<String>,
     pub theme_mode: Vec<String>,
+
+    // these are only provided for channel emotes
+    #[serde(default)]
+    pub tier: String,
+    #[serde(default)]
+    pub emote_type: String,
+    #[serde(default)]
+    pub emote_set_id: String,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Badges {
     pub set_id: String,
     pub versions: Vec<Versions>,
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
+impl Badges {
+    pub fn find<'a>(badges: &'a [Self], set_id: &str, id: &str) -> Option<&'a Versions> {
+        badges
+            .iter()
+            .find(|badge| badge.set_id == set_id)?
+            .versions
+            .iter()
+            .find(|version| version.id == id)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Versions {
     pub id: String,
     pub image_url_1x: String,
     pub image_url_2x: String,
     pub image_url_4x: String,
 }
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Cheermote {
+    pub prefix: String,
+    pub tiers: Vec<CheermoteTier>,
+}
+
+impl Cheermote {
+    /// The highest tier the amount of bits reaches
+    pub fn tier(&self, bits: u64) -> Option<&CheermoteTier> {
+        self.tiers
+            .iter()
+            .filter(|tier| tier.min_bits <= bits)
+            .max_by_key(|tier| tier.min_bits)
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CheermoteTier {
+    pub id: String,
+    pub min_bits: u64,
+    pub color: String,
+    pub images: CheermoteImages,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CheermoteImages {
+    pub dark: CheermoteTheme,
+    pub light: CheermoteTheme,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct CheermoteTheme {
+    pub animated: HashMap<String, String>,
+    #[serde(rename = "static")]
+    pub still: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chatters() {
+        let mut chatters: Chatters = [
+            (Kind::Viewer, "bob".to_string()),
+            (Kind::Viewer, "alice".to_string()),
+            (Kind::Moderator, "carol".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(chatters.count, 3);
+
+        chatters.insert(Kind::Vip, "bob");
+        assert_eq!(chatters.count, 3);
+        assert_eq!(chatters.get("bob"), Some(Kind::Vip));
+        assert_eq!(
+            chatters.iter().collect::<Vec<_>>(),
+            [
+                (Kind::Vip, "bob"),
+                (Kind::Moderator, "carol"),
+                (Kind::Viewer, "alice")
+            ]
+        );
+
+        assert_eq!(chatters.remove("carol"), Some(Kind::Moderator));
+        assert_eq!(chatters.remove("carol"), None);
+        assert_eq!(chatters.get("carol"), None);
+        assert_eq!(chatters.count, 2);
+    }
+
+    #[test]
+    fn replay_refuses_writes() {
+        let dir = std::env::temp_dir().join("kappachat-replay-refuses-writes");
+        let fixtures = Fixtures::Replay(dir.clone());
+        let helix = Client::fetch_oauth(Endpoints::default(), fixtures, "", "").unwrap();
+
+        // reads come from the fixtures
+        Fixtures::write(
+            &dir,
+            "streams",
+            &[("first", "1"), ("user_login", "c")],
+            r#"{"data":[]}"#,
+        )
+        .unwrap();
+        assert!(helix.get_stream_for("c").unwrap().is_none());
+
+        let err = helix.ban_user("1", "2", "3", None).unwrap_err();
+        assert!(matches!(err, Error::Replay(..)), "{err}");
+        let err = helix.delete_chat_message("1", "2", "3").unwrap_err();
+        assert!(matches!(err, Error::Replay(..)), "{err}");
+    }
+
+    #[test]
+    fn user_token_rejected() {
+        use std::io::{BufRead, BufReader, Write};
+
+        // a stand-in for helix and the id service that rejects every token
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let paths = Arc::new(Mutex::new(Vec::new()));
+
+        std::thread::spawn({
+            let paths = paths.clone();
+            move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                    let mut request = String::new();
+                    reader.read_line(&mut request).unwrap();
+                    let path = request.split(' ').nth(1).unwrap_or_default();
+                    paths.lock().push(path.to_string());
+
+                    let mut header = String::from("-");
+                    while !header.trim().is_empty() {
+                        header.clear();
+                        reader.read_line(&mut header).unwrap();
+                    }
+
+                    let body = r#"{"status":401,"message":"missing scope"}"#;
+                    write!(
+                        stream,
+                        "HTTP/1.1 401 X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                        body.len()
+                    )
+                    .unwrap();
+                }
+            }
+        });
+
+        let app = Client {
+            oauth: Arc::new(RwLock::new(OAuth::external("client", ""))),
+            limits: Arc::default(),
+            endpoints: Arc::new(Endpoints {
+                helix: format!("http://{addr}/helix"),
+                id: format!("http://{addr}/id"),
+                ..Endpoints::default()
+            }),
+            fixtures: Arc::default(),
+            agent: ureq::agent(),
+        };
+        let helix = app.for_user("client", "oauth:token");
+
+        // the rejection is returned, rather than a failed refresh
+        let err = helix.delete_chat_message("1", "2", "3").unwrap_err();
+        assert!(
+            matches!(&err, Error::Auth(msg) if msg == "missing scope"),
+            "{err}"
+        );
+        assert!(paths.lock().iter().all(|path| path.starts_with("/helix/")));
+    }
+}
@@ -0,0 +1,243 @@
+use egui::Color32;
+use regex::{Regex, RegexBuilder};
+
+use crate::twitch::Privmsg;
+
+/// What part of a message a rule looks at
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum HighlightMatch {
+    /// A regex over the message text
+    #[default]
+    Text,
+    /// The sender's login
+    Sender,
+    /// A badge set, e.g. `moderator`
+    Badge,
+    /// A tag, e.g. `first-msg` or `msg-id=highlighted-message`
+    Tag,
+}
+
+impl HighlightMatch {
+    pub const ALL: [Self; 4] = [Self::Text, Self::Sender, Self::Badge, Self::Tag];
+
+    pub const fn display(&self) -> &'static str {
+        match self {
+            Self::Text => "Text",
+            Self::Sender => "Sender",
+            Self::Badge => "Badge",
+            Self::Tag => "Tag",
+        }
+    }
+
+    pub const fn hint(&self) -> &'static str {
+        match self {
+            Self::Text => "a regex, e.g. \\bkappa\\b",
+            Self::Sender => "a login",
+            Self::Badge => "a badge, e.g. moderator",
+            Self::Tag => "a tag, e.g. first-msg or name=value",
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+pub struct HighlightRule {
+    pub kind: HighlightMatch,
+    pub pattern: String,
+    pub color: [u8; 3],
+    pub priority: i32,
+    pub copy_to_mentions: bool,
+    #[serde(skip)]
+    regex: Option<Result<Regex, String>>,
+}
+
+impl Default for HighlightRule {
+    fn default() -> Self {
+        let [r, g, b, _] = crate::TWITCH_COLOR.to_array();
+        Self {
+            kind: HighlightMatch::default(),
+            pattern: String::new(),
+            color: [r, g, b],
+            priority: 0,
+            copy_to_mentions: false,
+            regex: None,
+        }
+    }
+}
+
+impl HighlightRule {
+    /// Rebuilds the regex, this should be called when the pattern changes
+    pub fn compile(&mut self) {
+        self.regex =
+            (self.kind == HighlightMatch::Text && !self.pattern.trim().is_empty()).then(|| {
+                RegexBuilder::new(&self.pattern)
+                    .case_insensitive(true)
+                    .build()
+                    .map_err(|err| err.to_string())
+            });
+    }
+
+    /// The reason the pattern can't be used, if any
+    pub fn error(&self) -> Option<&str> {
+        self.regex.as_ref()?.as_ref().err().map(|s| &**s)
+    }
+
+    pub fn matches(&self, pm: &Privmsg<'_>) -> bool {
+        let pattern = self.pattern.trim();
+        if pattern.is_empty() {
+            return false;
+        }
+
+        match self.kind {
+            HighlightMatch::Text => matches!(&self.regex, Some(Ok(re)) if re.is_match(pm.data)),
+            HighlightMatch::Sender => pm.sender.eq_ignore_ascii_case(pattern),
+            HighlightMatch::Badge => pm
+                .badges()
+                .any(|(set_id, _)| set_id.eq_ignore_ascii_case(pattern)),
+            HighlightMatch::Tag => match pattern.split_once('=') {
+                Some((key, value)) => pm.tags.get(key) == Some(value),
+                // boolean tags are sent as 0 or 1
+                None => pm
+                    .tags
+                    .get(pattern)
+                    .filter(|value| !value.is_empty() && *value != "0")
+                    .is_some(),
+            },
+        }
+    }
+
+    fn highlight(&self) -> Highlight {
+        let [r, g, b] = self.color;
+        Highlight {
+            color: Color32::from_rgb(r, g, b),
+            copy_to_mentions: self.copy_to_mentions,
+        }
+    }
+}
+
+/// How a chat line should be highlighted
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Highlight {
+    pub color: Color32,
+    pub copy_to_mentions: bool,
+}
+
+impl Highlight {
+    /// Our own name being mentioned
+    pub const MENTION: Self = Self {
+        color: crate::TWITCH_COLOR,
+        copy_to_mentions: true,
+    };
+}
+
+/// User-defined highlight rules
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(from = "Vec<HighlightRule>", into = "Vec<HighlightRule>")]
+pub struct HighlightRules {
+    pub rules: Vec<HighlightRule>,
+}
+
+impl From<Vec<HighlightRule>> for HighlightRules {
+    fn from(mut rules: Vec<HighlightRule>) -> Self {
+        rules.iter_mut().for_each(HighlightRule::compile);
+        Self { rules }
+    }
+}
+
+impl From<HighlightRules> for Vec<HighlightRule> {
+    fn from(rules: HighlightRules) -> Self {
+        rules.rules
+    }
+}
+
+impl HighlightRules {
+    /// The highlight from the matching rule with the highest priority.
+    ///
+    /// Mentions of `our_name` are highlighted unless a rule matches
+    pub fn find(&self, pm: &Privmsg<'_>, our_name: Option<&str>) -> Option<Highlight> {
+        if our_name.is_some_and(|name| pm.sender.eq_ignore_ascii_case(name)) {
+            return None;
+        }
+
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(pm))
+            // the first rule wins a tie
+            .rev()
+            .max_by_key(|rule| rule.priority)
+            .map(HighlightRule::highlight)
+            .or_else(|| {
+                our_name
+                    .filter(|name| Self::mentions(pm.data, name))
+                    .map(|_| Highlight::MENTION)
+            })
+    }
+
+    fn mentions(data: &str, name: &str) -> bool {
+        data.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::twitch::Message;
+
+    fn rule(kind: HighlightMatch, pattern: &str, color: u8, priority: i32) -> HighlightRule {
+        let mut rule = HighlightRule {
+            kind,
+            pattern: pattern.to_string(),
+            color: [color, 0, 0],
+            priority,
+            ..HighlightRule::default()
+        };
+        rule.compile();
+        rule
+    }
+
+    fn find(rules: &[HighlightRule], raw: &str, our_name: Option<&str>) -> Option<u8> {
+        let rules = HighlightRules::from(rules.to_vec());
+        let msg = Message::parse(raw).unwrap();
+        let highlight = rules.find(&msg.as_privmsg().unwrap(), our_name)?;
+        Some(highlight.color.r())
+    }
+
+    const HELLO: &str =
+        "@first-msg=0;badges=moderator/1 :bob!bob@bob.tmi.twitch.tv PRIVMSG #c :hello alice";
+
+    #[test]
+    fn priority() {
+        let text = rule(HighlightMatch::Text, "hello", 1, 0);
+        let sender = rule(HighlightMatch::Sender, "BOB", 2, 0);
+        let badge = rule(HighlightMatch::Badge, "moderator", 3, 5);
+
+        // the first rule wins a tie
+        assert_eq!(find(&[text.clone(), sender.clone()], HELLO, None), Some(1));
+        assert_eq!(find(&[sender.clone(), text.clone()], HELLO, None), Some(2));
+        assert_eq!(find(&[text, sender, badge], HELLO, None), Some(3));
+    }
+
+    #[test]
+    fn mentions() {
+        let mention = Some(Highlight::MENTION.color.r());
+        assert_eq!(find(&[], HELLO, Some("alice")), mention);
+        assert_eq!(find(&[], HELLO, Some("ali")), None);
+        // our own messages aren't highlighted
+        assert_eq!(find(&[], HELLO, Some("bob")), None);
+
+        // a rule wins over a mention
+        let rule = rule(HighlightMatch::Text, "hello", 1, 0);
+        assert_eq!(find(&[rule], HELLO, Some("alice")), Some(1));
+    }
+
+    #[test]
+    fn tags() {
+        let first = rule(HighlightMatch::Tag, "first-msg", 1, 0);
+        assert_eq!(find(std::slice::from_ref(&first), HELLO, None), None);
+        let raw = HELLO.replace("first-msg=0", "first-msg=1");
+        assert_eq!(find(&[first], &raw, None), Some(1));
+
+        let value = rule(HighlightMatch::Tag, "badges=moderator/1", 2, 0);
+        assert_eq!(find(&[value], HELLO, None), Some(2));
+    }
+}
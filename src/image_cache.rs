@@ -3,9 +3,12 @@ use std::collections::HashMap;
 use egui_extras::RetainedImage;
 use uuid::Uuid;
 
+use crate::store::{Image, ImageStore};
+
 #[derive(Default)]
 pub struct ImageCache {
     pub map: HashMap<Uuid, RetainedImage>,
+    urls: HashMap<String, Uuid>,
 }
 
 impl ImageCache {
@@ -17,6 +20,17 @@ impl ImageCache {
         self.map.contains_key(&id)
     }
 
+    /// Gets a stable id for the url, reusing the one in the disk cache if it exists
+    pub fn id_for_url(&mut self, url: &str) -> Uuid {
+        if let Some(id) = self.urls.get(url) {
+            return *id;
+        }
+
+        let id = ImageStore::<Image>::get_id(url).unwrap_or_else(Uuid::new_v4);
+        self.urls.insert(url.to_string(), id);
+        id
+    }
+
     pub fn add(&mut self, id: Uuid, image: RetainedImage) {
         log::debug!("image cache: adding: {id}");
         self.map.insert(id, image);
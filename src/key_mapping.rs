@@ -25,7 +25,7 @@ impl Default for KeyMapping {
         }
 
         #[rustfmt::skip]
-        const DEFAULT: [(Chord, KeyAction); 28] = [
+        const DEFAULT: [(Chord, KeyAction); 29] = [
             (key!(F1), SwitchToMain),
             (key!(F2), SwitchToSettings),
             (key!(ctrl L), ToggleLineMode),
@@ -45,6 +45,7 @@ impl Default for KeyMapping {
             (key!(ctrl Num9), SwitchTab8), (key!(alt Num9), SwitchTab8),
             (key!(ctrl N), NextTab),
             (key!(ctrl P), PreviousTab),
+            (key!(ctrl J), JumpToLastRead),
         ];
 
         let map = DEFAULT.into_iter().collect::<Vec<_>>();
@@ -100,8 +101,7 @@ impl KeyMapping {
 
     pub fn iter(
         &self,
-    ) -> impl Iterator<Item = &(Chord, KeyAction)> //
-           + ExactSizeIterator
+    ) -> impl ExactSizeIterator<Item = &(Chord, KeyAction)> //
            + DoubleEndedIterator
            + '_ {
         self.map.iter()
@@ -147,14 +147,17 @@ impl<'de> ::serde::Deserialize<'de> for KeyMapping {
                     reverse: vec![],
                 };
                 let mut seen = HashSet::<Chord>::new();
+                let mut actions = HashSet::<KeyAction>::new();
 
                 while let Some((val, key)) = map.next_entry_seed(
                     std::marker::PhantomData::<&str>,
                     std::marker::PhantomData::<Vec<&str>>,
                 )? {
+                    let val: KeyAction = val.parse().map_err(A::Error::custom)?;
+                    actions.insert(val);
+
                     for key in key {
                         let key = key.parse().map_err(A::Error::custom)?;
-                        let val: KeyAction = val.parse().map_err(A::Error::custom)?;
 
                         if !seen.insert(key) {
                             return Err(A::Error::custom(format!(
@@ -168,7 +171,22 @@ impl<'de> ::serde::Deserialize<'de> for KeyMapping {
                     }
                 }
 
+                // actions added since this was saved get their default chords, if they're free
+                for (key, val) in Self::Value::default().map {
+                    if !actions.contains(&val) && seen.insert(key) {
+                        this.map.push((key, val));
+                    }
+                }
+
                 this.reverse = Self::Value::make_reverse(&this.map);
+                // actions that were unbound on purpose stay that way
+                for action in actions {
+                    if this.find_chords_reverse(&action).is_none() {
+                        this.reverse.push((action, vec![]));
+                    }
+                }
+                this.reverse.sort_by_key(|(action, _)| *action);
+
                 Ok(this)
             }
         }
@@ -184,13 +202,8 @@ impl ::serde::Serialize for KeyMapping {
     {
         use ::serde::ser::SerializeMap as _;
 
-        let mapping =
-            self.map
-                .iter()
-                .fold(BTreeMap::<_, Vec<_>>::new(), |mut map, (chord, action)| {
-                    map.entry(action).or_default().push(chord);
-                    map
-                });
+        // this includes unbound actions, so they aren't given their defaults when loaded
+        let mapping = &self.reverse;
 
         let mut map = serializer.serialize_map(Some(mapping.len()))?;
         for (k, v) in mapping {
@@ -467,4 +480,43 @@ define_key! {
     SwitchTab9       => "Switch to Tab #9"
     NextTab          => "Switches to the next tab, wrapping around"
     PreviousTab      => "Switches to the previous tab, wrapping around"
+    JumpToLastRead   => "Scrolls the current tab to where you stopped reading"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_merges_defaults() {
+        let mut saved = serde_json::to_value(KeyMapping::default()).unwrap();
+        let saved_map = saved.as_object_mut().unwrap();
+        // saved before the action existed
+        saved_map.remove(KeyAction::JumpToLastRead.display());
+        // unbound on purpose
+        saved_map.insert(
+            KeyAction::ToggleTabBar.display().into(),
+            Vec::<()>::new().into(),
+        );
+
+        let mapping: KeyMapping = serde_json::from_str(&saved.to_string()).unwrap();
+        let ctrl = Modifiers {
+            ctrl: true,
+            ..Modifiers::NONE
+        };
+        assert_eq!(
+            mapping.find(egui::Key::J, ctrl),
+            Some(KeyAction::JumpToLastRead)
+        );
+        assert_eq!(mapping.find(egui::Key::F4, Modifiers::NONE), None);
+        assert_eq!(
+            mapping.find_chords_reverse(&KeyAction::ToggleTabBar),
+            Some(&vec![])
+        );
+
+        // and it's still unbound after saving it again
+        let saved = serde_json::to_string(&mapping).unwrap();
+        let mapping: KeyMapping = serde_json::from_str(&saved).unwrap();
+        assert_eq!(mapping.find(egui::Key::F4, Modifiers::NONE), None);
+    }
 }
@@ -23,30 +23,51 @@ impl RequestPaint for NoopRepaint {}
 
 pub mod app;
 mod channel;
+mod channel_assets;
 mod config;
+mod endpoints;
 mod fetch;
+mod filter;
+mod fixtures;
 pub mod font_icon;
 pub mod helix;
+mod highlight;
 mod image_cache;
 mod interaction;
 pub mod kappas;
 mod key_mapping;
+mod login;
+mod mod_log;
+mod moderation;
 mod queue;
 pub mod state;
+mod stream_event;
+mod stream_poller;
 mod task_queue;
+mod token;
 pub mod twitch;
 mod user_list_updater;
+mod whispers;
 pub mod widgets;
 
 pub use app::App;
 pub use channel::Channel;
+use channel_assets::{ChannelAssets, ChannelAssetsLoader};
 pub use config::EnvConfig;
+pub use endpoints::Endpoints;
 pub use fetch::{FetchImage, FetchQueue};
+pub use filter::{FilterCounts, FilterPattern, FilterReason, MessageFilters};
+pub use fixtures::Fixtures;
+pub use highlight::{Highlight, HighlightMatch, HighlightRule, HighlightRules};
 pub use image_cache::ImageCache;
 pub use interaction::Interaction;
 pub use key_mapping::{Chord, KeyAction, KeyHelper, KeyMapping};
+use mod_log::ModLogEntry;
 pub use queue::Queue;
+use stream_event::StreamEvent;
+use stream_poller::StreamPoller;
 pub use task_queue::TaskQueue;
+use token::TokenManager;
 use user_list_updater::UserListUpdater;
 
 mod store;
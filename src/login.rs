@@ -0,0 +1,346 @@
+use std::time::{Duration, Instant};
+
+use flume::{Receiver, Sender};
+
+use crate::{token::TokenInfo, RequestPaint};
+
+/// The scopes requested when logging in
+pub const SCOPES: &[&str] = &[
+    "chat:read",
+    "chat:edit",
+    "whispers:read",
+    "whispers:edit",
+    "user:manage:whispers",
+    "moderator:read:chatters",
+    "moderator:manage:banned_users",
+    "moderator:manage:chat_messages",
+    "moderator:manage:chat_settings",
+];
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    pub expires_in: u64,
+    pub interval: u64,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct UserToken {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_in: u64,
+    #[serde(default)]
+    pub scope: Vec<String>,
+}
+
+/// The result of trying to exchange a device code
+#[derive(Debug)]
+pub enum Exchange {
+    Token(UserToken),
+    /// The user hasn't finished authorizing the app yet
+    Pending,
+    /// We're polling too quickly
+    SlowDown,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedIn {
+    pub login: String,
+    pub token: UserToken,
+}
+
+/// The device code grant flow
+///
+/// See <https://dev.twitch.tv/docs/authentication/getting-tokens-oauth/#device-code-grant-flow>
+pub struct DeviceFlow<'a> {
+    agent: ureq::Agent,
+    base: &'a str,
+    client_id: &'a str,
+}
+
+impl<'a> DeviceFlow<'a> {
+    pub const fn new(agent: ureq::Agent, base: &'a str, client_id: &'a str) -> Self {
+        Self {
+            agent,
+            base,
+            client_id,
+        }
+    }
+
+    pub fn request_code(&self, scopes: &[&str]) -> anyhow::Result<DeviceCode> {
+        anyhow::ensure!(!self.client_id.is_empty(), "twitch client id was empty");
+
+        let scopes = scopes.join(" ");
+        let resp = self
+            .agent
+            .post(&format!("{}/device", self.base))
+            .send_form(&[("client_id", self.client_id), ("scopes", &scopes)])?;
+
+        Ok(resp.into_json()?)
+    }
+
+    /// Tries to exchange the device code for a token
+    pub fn try_exchange(&self, code: &DeviceCode, scopes: &[&str]) -> anyhow::Result<Exchange> {
+        let scopes = scopes.join(" ");
+        let resp = self
+            .agent
+            .post(&format!("{}/token", self.base))
+            .send_form(&[
+                ("client_id", self.client_id),
+                ("scopes", &scopes),
+                ("device_code", &code.device_code),
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+            ]);
+
+        match resp {
+            Ok(resp) => Ok(Exchange::Token(resp.into_json()?)),
+            Err(ureq::Error::Status(400, resp)) => {
+                let Error { message } = resp.into_json()?;
+                match &*message {
+                    "authorization_pending" => Ok(Exchange::Pending),
+                    "slow_down" => Ok(Exchange::SlowDown),
+                    _ => anyhow::bail!("cannot log in: {message}"),
+                }
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Gets a new token with the refresh token from an earlier login
+    pub fn refresh(&self, refresh_token: &str) -> anyhow::Result<UserToken> {
+        anyhow::ensure!(!self.client_id.is_empty(), "twitch client id was empty");
+
+        let resp = self
+            .agent
+            .post(&format!("{}/token", self.base))
+            .send_form(&[
+                ("client_id", self.client_id),
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ]);
+
+        match resp {
+            Ok(resp) => Ok(resp.into_json()?),
+            Err(ureq::Error::Status(400 | 401, resp)) => {
+                let Error { message } = resp.into_json()?;
+                anyhow::bail!("cannot refresh the token: {message}")
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Error {
+    message: String,
+}
+
+pub enum LoginEvent {
+    Code(DeviceCode),
+    Done(Result<LoggedIn, String>),
+}
+
+/// Runs the device code flow in the background
+pub struct Login {
+    events: Receiver<LoginEvent>,
+    code: Option<DeviceCode>,
+}
+
+impl Login {
+    const SLOW_DOWN: Duration = Duration::from_secs(5);
+
+    pub fn start(
+        base: impl ToString,
+        client_id: impl ToString,
+        repaint: impl RequestPaint + 'static,
+    ) -> Self {
+        let (tx, events) = flume::unbounded();
+        let (base, client_id) = (base.to_string(), client_id.to_string());
+
+        std::thread::spawn(move || {
+            let flow = DeviceFlow::new(ureq::agent(), &base, &client_id);
+            let res = Self::run(&flow, &tx).map_err(|err| err.to_string());
+            let _ = tx.send(LoginEvent::Done(res));
+            repaint.request_repaint();
+        });
+
+        Self { events, code: None }
+    }
+
+    pub fn code(&self) -> Option<&DeviceCode> {
+        self.code.as_ref()
+    }
+
+    /// Returns the result once the login has finished
+    pub fn poll(&mut self) -> Option<Result<LoggedIn, String>> {
+        for event in self.events.try_iter() {
+            match event {
+                LoginEvent::Code(code) => {
+                    self.code.replace(code);
+                }
+                LoginEvent::Done(res) => return Some(res),
+            }
+        }
+        None
+    }
+
+    fn run(flow: &DeviceFlow<'_>, events: &Sender<LoginEvent>) -> anyhow::Result<LoggedIn> {
+        let code = flow.request_code(SCOPES)?;
+        let mut interval = Duration::from_secs(code.interval.max(1));
+        let expires = Duration::from_secs(code.expires_in);
+        events.send(LoginEvent::Code(code.clone()))?;
+
+        let start = Instant::now();
+        while start.elapsed() < expires {
+            std::thread::sleep(interval);
+
+            // the login was cancelled
+            if events.is_disconnected() {
+                anyhow::bail!("the login was cancelled")
+            }
+
+            match flow.try_exchange(&code, SCOPES)? {
+                Exchange::Token(token) => {
+                    let info = TokenInfo::validate(&flow.agent, flow.base, &token.access_token)?;
+                    return Ok(LoggedIn {
+                        login: info.login,
+                        token,
+                    });
+                }
+                Exchange::Pending => {}
+                // rfc 8628 says to wait 5 more seconds from now on
+                Exchange::SlowDown => interval += Self::SLOW_DOWN,
+            }
+        }
+
+        anyhow::bail!("the device code expired")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::Endpoints;
+
+    /// A stand-in for the id service, `token` is the response for each exchange in turn
+    fn serve(token: Vec<(u16, &'static str)>) -> Endpoints {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        std::thread::spawn(move || {
+            let mut token = token.into_iter();
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut request = String::new();
+                reader.read_line(&mut request).unwrap();
+                let path = request.split(' ').nth(1).unwrap_or_default().to_string();
+
+                let mut len = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((key, value)) = header.split_once(':') {
+                        if key.eq_ignore_ascii_case("content-length") {
+                            len = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+
+                let (status, body) = match &*path {
+                    "/device" => (
+                        200,
+                        r#"{"device_code":"device","user_code":"ABCD","verification_uri":"http://localhost/activate","expires_in":30,"interval":0}"#,
+                    ),
+                    "/token" => token.next().unwrap_or((500, "{}")),
+                    "/validate" => (
+                        200,
+                        r#"{"client_id":"client","login":"museun","scopes":["chat:read"],"user_id":"1","expires_in":3600}"#,
+                    ),
+                    _ => (404, "{}"),
+                };
+
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+        });
+
+        Endpoints {
+            id: format!("http://{addr}"),
+            ..Endpoints::default()
+        }
+    }
+
+    const PENDING: (u16, &str) = (400, r#"{"status":400,"message":"authorization_pending"}"#);
+    const SLOW_DOWN: (u16, &str) = (400, r#"{"status":400,"message":"slow_down"}"#);
+    const TOKEN: (u16, &str) = (
+        200,
+        r#"{"access_token":"access","refresh_token":"refresh","expires_in":3600,"scope":["chat:read"]}"#,
+    );
+
+    #[test]
+    fn device_flow() {
+        let endpoints = serve(vec![PENDING, TOKEN]);
+        let flow = DeviceFlow::new(ureq::agent(), &endpoints.id, "client");
+
+        let (tx, rx) = flume::unbounded();
+        let logged_in = Login::run(&flow, &tx).unwrap();
+        assert_eq!(logged_in.login, "museun");
+        assert_eq!(logged_in.token.access_token, "access");
+        assert_eq!(logged_in.token.refresh_token.as_deref(), Some("refresh"));
+
+        let code = match rx.try_recv().unwrap() {
+            LoginEvent::Code(code) => code,
+            LoginEvent::Done(..) => panic!("expected the device code first"),
+        };
+        assert_eq!(code.user_code, "ABCD");
+    }
+
+    #[test]
+    fn exchange() {
+        let endpoints = serve(vec![
+            PENDING,
+            SLOW_DOWN,
+            (400, r#"{"message":"access_denied"}"#),
+        ]);
+        let flow = DeviceFlow::new(ureq::agent(), &endpoints.id, "client");
+        let code = flow.request_code(SCOPES).unwrap();
+
+        assert!(matches!(
+            flow.try_exchange(&code, SCOPES).unwrap(),
+            Exchange::Pending
+        ));
+        assert!(matches!(
+            flow.try_exchange(&code, SCOPES).unwrap(),
+            Exchange::SlowDown
+        ));
+        assert!(flow.try_exchange(&code, SCOPES).is_err());
+    }
+
+    #[test]
+    fn refresh() {
+        let endpoints = serve(vec![TOKEN]);
+        let flow = DeviceFlow::new(ureq::agent(), &endpoints.id, "client");
+
+        let token = flow.refresh("refresh").unwrap();
+        assert_eq!(token.access_token, "access");
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{store::EventStore, twitch::RoomSettings};
+
+/// Something a moderator did in a channel
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModLogEntry {
+    ClearChat,
+    Timeout {
+        login: String,
+        secs: u64,
+    },
+    Ban {
+        login: String,
+    },
+    DeleteMessage {
+        login: Option<String>,
+        message: Option<String>,
+    },
+    /// The result of a chat command we did through Helix
+    Moderation {
+        action: String,
+        error: Option<String>,
+    },
+    Mode {
+        mode: String,
+        enabled: bool,
+        value: Option<u64>,
+    },
+}
+
+impl ModLogEntry {
+    const KIND: &'static str = "mod_log";
+
+    /// The chat modes that changed
+    pub fn diff_room(old: &RoomSettings, new: &RoomSettings) -> Vec<Self> {
+        let mut entries = vec![];
+        let mut mode = |mode: &str, old: Option<u64>, new: Option<u64>| {
+            if old != new {
+                entries.push(Self::Mode {
+                    mode: mode.to_string(),
+                    enabled: new.is_some(),
+                    value: new.filter(|&value| value > 0),
+                })
+            }
+        };
+
+        let flag = |enabled: bool| enabled.then_some(0);
+        mode("emote only", flag(old.emote_only), flag(new.emote_only));
+        // followers-only is in minutes
+        mode(
+            "followers only",
+            old.followers_only.map(|m| m * 60),
+            new.followers_only.map(|m| m * 60),
+        );
+        mode("unique chat", flag(old.r9k), flag(new.r9k));
+        mode("slow mode", old.slow, new.slow);
+        mode("subscribers only", flag(old.subs_only), flag(new.subs_only));
+
+        entries
+    }
+
+    pub fn store(&self, channel: &str, at: i64) -> bool {
+        EventStore::add(Self::KIND, Self::key(channel), at, self)
+    }
+
+    pub fn since(channel: &str, since: i64) -> Vec<(i64, Self)> {
+        EventStore::since(Self::KIND, Self::key(channel), since)
+    }
+
+    fn key(channel: &str) -> &str {
+        channel.strip_prefix('#').unwrap_or(channel)
+    }
+}
+
+impl std::fmt::Display for ModLogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClearChat => f.write_str("chat was cleared"),
+            Self::Timeout { login, secs } => {
+                write!(
+                    f,
+                    "{login} was timed out for {}",
+                    crate::format_seconds(*secs)
+                )
+            }
+            Self::Ban { login } => write!(f, "{login} was banned"),
+            Self::DeleteMessage { login, message } => {
+                match login {
+                    Some(login) => write!(f, "a message from {login} was deleted")?,
+                    None => f.write_str("a message was deleted")?,
+                }
+                match message {
+                    Some(message) => write!(f, ": {message}"),
+                    None => Ok(()),
+                }
+            }
+            Self::Moderation {
+                action,
+                error: None,
+            } => write!(f, "you did {action}"),
+            Self::Moderation {
+                action,
+                error: Some(error),
+            } => write!(f, "{action} failed: {error}"),
+            Self::Mode {
+                mode,
+                enabled: true,
+                value: Some(value),
+            } => write!(
+                f,
+                "{mode} was turned on ({})",
+                crate::format_seconds(*value)
+            ),
+            Self::Mode {
+                mode,
+                enabled: true,
+                ..
+            } => write!(f, "{mode} was turned on"),
+            Self::Mode { mode, .. } => write!(f, "{mode} was turned off"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(mode: &str, enabled: bool, value: Option<u64>) -> ModLogEntry {
+        ModLogEntry::Mode {
+            mode: mode.to_string(),
+            enabled,
+            value,
+        }
+    }
+
+    #[test]
+    fn diff_room() {
+        let old = RoomSettings::default();
+        assert!(ModLogEntry::diff_room(&old, &old).is_empty());
+
+        let new = RoomSettings {
+            emote_only: true,
+            followers_only: Some(10),
+            slow: Some(30),
+            ..old
+        };
+        assert_eq!(
+            ModLogEntry::diff_room(&old, &new),
+            [
+                mode("emote only", true, None),
+                // minutes are stored as seconds
+                mode("followers only", true, Some(600)),
+                mode("slow mode", true, Some(30)),
+            ]
+        );
+
+        // followers-only for any follower has no duration
+        let any = RoomSettings {
+            followers_only: Some(0),
+            ..old
+        };
+        assert_eq!(
+            ModLogEntry::diff_room(&new, &any),
+            [
+                mode("emote only", false, None),
+                mode("followers only", true, None),
+                mode("slow mode", false, None),
+            ]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use poll_promise::Promise;
+
+use crate::helix::{self, IdOrLogin};
+
+pub const BANS_SCOPE: &str = "moderator:manage:banned_users";
+pub const CHAT_SCOPE: &str = "moderator:manage:chat_messages";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModAction {
+    Delete { login: String, id: uuid::Uuid },
+    Timeout { login: String, secs: u64 },
+    Ban { login: String },
+    Unban { login: String },
+}
+
+impl ModAction {
+    pub fn login(&self) -> &str {
+        match self {
+            Self::Delete { login, .. }
+            | Self::Timeout { login, .. }
+            | Self::Ban { login }
+            | Self::Unban { login } => login,
+        }
+    }
+
+    /// The scope Helix needs for this
+    pub const fn scope(&self) -> &'static str {
+        match self {
+            Self::Delete { .. } => CHAT_SCOPE,
+            _ => BANS_SCOPE,
+        }
+    }
+
+    /// Whether irc tells us when this happens, with a CLEARCHAT or CLEARMSG
+    pub const fn is_reported_by_irc(&self) -> bool {
+        !matches!(self, Self::Unban { .. })
+    }
+
+    /// The chat command for this, for when we can't use Helix
+    pub fn command(&self) -> String {
+        match self {
+            Self::Delete { id, .. } => format!("/delete {id}"),
+            Self::Timeout { login, secs } => format!("/timeout {login} {secs}"),
+            Self::Ban { login } => format!("/ban {login}"),
+            Self::Unban { login } => format!("/unban {login}"),
+        }
+    }
+
+    /// Does this with a client using a moderator's token
+    pub fn run(
+        &self,
+        helix: &helix::Client,
+        broadcaster_id: &str,
+        moderator_id: &str,
+    ) -> helix::Result<()> {
+        if let Self::Delete { id, .. } = self {
+            let id = id.to_string();
+            return helix.delete_chat_message(broadcaster_id, moderator_id, &id);
+        }
+
+        let login = self.login();
+        let user = helix
+            .get_users([IdOrLogin::Login(login)])?
+            .pop()
+            .ok_or_else(|| helix::Error::NotFound(format!("unknown user: {login}")))?;
+
+        match self {
+            Self::Timeout { secs, .. } => {
+                helix.ban_user(broadcaster_id, moderator_id, &user.id, Some(*secs))
+            }
+            Self::Ban { .. } => helix.ban_user(broadcaster_id, moderator_id, &user.id, None),
+            Self::Unban { .. } => helix.unban_user(broadcaster_id, moderator_id, &user.id),
+            Self::Delete { .. } => unreachable!(),
+        }
+    }
+}
+
+impl std::fmt::Display for ModAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Delete { login, .. } => write!(f, "deleted a message from {login}"),
+            Self::Timeout { login, secs } => {
+                write!(f, "timed out {login} for {}", crate::format_seconds(*secs))
+            }
+            Self::Ban { login } => write!(f, "banned {login}"),
+            Self::Unban { login } => write!(f, "unbanned {login}"),
+        }
+    }
+}
+
+/// Moderation actions being done through Helix
+#[derive(Default)]
+pub struct Moderation {
+    pending: Vec<(String, ModAction, Promise<helix::Result<()>>)>,
+}
+
+impl Moderation {
+    pub fn push(&mut self, channel: &str, action: ModAction, promise: Promise<helix::Result<()>>) {
+        self.pending.push((channel.to_string(), action, promise))
+    }
+
+    /// The finished actions, and the channel they were done in
+    pub fn poll(&mut self) -> Vec<(String, ModAction, helix::Result<()>)> {
+        let (done, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(.., promise)| promise.ready().is_some());
+        self.pending = pending;
+
+        done.into_iter()
+            .map(|(channel, action, promise)| (channel, action, promise.block_and_take()))
+            .collect()
+    }
+}
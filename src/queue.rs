@@ -37,7 +37,11 @@ impl<T> Queue<T> {
         self.queue.is_empty()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = &T> + ExactSizeIterator + DoubleEndedIterator {
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &T> + DoubleEndedIterator {
         self.queue.iter()
     }
+
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = &mut T> + DoubleEndedIterator {
+        self.queue.iter_mut()
+    }
 }
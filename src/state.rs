@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, VecDeque},
     hash::{Hash, Hasher},
+    time::Instant,
 };
 
 use eframe::epaint::ahash::HashSet;
@@ -12,14 +13,17 @@ use uuid::Uuid;
 
 use crate::{
     helix,
+    moderation::Moderation,
     store::Image,
     twitch,
+    whispers::Whispers,
     widgets::{
         state::{self, ChatViewState},
         MainView, Position,
     },
-    Channel, EnvConfig, FetchQueue, ImageCache, Interaction, KeyMapping, Queue, RequestPaint,
-    UserListUpdater,
+    Channel, ChannelAssets, ChannelAssetsLoader, Endpoints, EnvConfig, FetchQueue, HighlightRules,
+    ImageCache, Interaction, KeyMapping, MessageFilters, Queue, RequestPaint, StreamPoller,
+    TokenManager, UserListUpdater,
 };
 
 #[derive(Default)]
@@ -44,10 +48,14 @@ impl ViewState {
 #[derive(Default)]
 pub struct State {
     pub config: EnvConfig,
+    pub endpoints: Endpoints,
     pub key_mapping: KeyMapping,
     pub pixels_per_point: f32,
 
     pub channels: Vec<Channel>,
+    pub ignored_users: BTreeSet<String>,
+    pub highlights: HighlightRules,
+    pub filters: MessageFilters,
 
     pub chat_view_state: state::ChatViewState,
 
@@ -55,6 +63,7 @@ pub struct State {
     pub twitch_channels: state::TwitchChannelsState,
     pub twitch_settings: state::TwitchSettingsState,
     pub keybind_state: state::KeybindingsState,
+    pub filter_settings: state::FilterSettingsState,
     pub start_state: state::StartState,
     pub main_view: state::MainViewState,
 
@@ -64,6 +73,7 @@ pub struct State {
     pub messages: Queue<twitch::Message>,
 
     pub emote_map: HashMap<String, String>,
+    pub channel_assets: HashMap<u64, ChannelAssets>,
     pub images: ImageCache,
     pub requested_images: HashSet<Uuid>,
 
@@ -84,11 +94,23 @@ impl State {
 }
 
 pub struct Runtime {
-    pub helix: Promise<helix::Client>,
+    pub helix: Promise<helix::Result<helix::Client>>,
     pub fetch: FetchQueue<Image>,
     pub chatters_update: UserListUpdater,
+    pub channel_assets: ChannelAssetsLoader,
+    pub streams: StreamPoller,
+    pub moderation: Moderation,
     pub global_badges: Promise<Vec<helix::Badges>>,
     pub helix_ready: flume::Sender<helix::Client>,
+    pub tokens: TokenManager,
+    // when we sent our recent messages, twitch rate limits them across every channel
+    pub sent: VecDeque<Instant>,
+}
+
+impl Runtime {
+    pub fn helix(&self) -> Option<&helix::Client> {
+        self.helix.ready()?.as_ref().ok()
+    }
 }
 
 pub struct AppState {
@@ -113,7 +135,9 @@ impl AppState {
         self.identity().user_name == name
     }
 
-    pub fn send_message(&self, target: &str, data: &str) {
+    /// Sends a PRIVMSG, it counts towards the rate limit the edit boxes check
+    pub fn send_message(&mut self, target: &str, data: &str) {
+        self.runtime.sent.push_back(Instant::now());
         self.send_raw_fmt(format_args!("PRIVMSG {target} :{data}\r\n"))
     }
 
@@ -137,7 +161,7 @@ impl AppState {
 
         let (client, identity) = {
             let reg = twitch::Registration {
-                address: "irc.chat.twitch.tv:6667",
+                address: &self.state.endpoints.irc,
                 nick: &self.state.config.twitch_name,
                 pass: &self.state.config.twitch_oauth_token,
             };
@@ -163,10 +187,11 @@ impl AppState {
 
 impl AppState {
     pub fn new(
-        repaint: impl RequestPaint + 'static,
+        repaint: impl RequestPaint + Clone + 'static,
         kappas: Vec<egui_extras::RetainedImage>,
         persist: PersistState,
-        helix: Promise<helix::Client>,
+        endpoints: Endpoints,
+        helix: Promise<helix::Result<helix::Client>>,
         dark_image_mask: RetainedImage,
     ) -> Self {
         fn default<T: Default>() -> T {
@@ -180,12 +205,17 @@ impl AppState {
             state: State {
                 pixels_per_point: persist.pixels_per_point,
                 channels: persist.channels,
+                ignored_users: persist.ignored_users,
+                highlights: persist.highlights,
+                filters: persist.filters,
                 config: persist.env_config,
+                endpoints: endpoints.clone(),
                 key_mapping: persist.key_mapping,
                 chat_view_state: ChatViewState {
                     tab_bar_position: persist.tab_bar_position,
                     image_size: persist.tab_bar_image_size,
                     show_mask: persist.show_image_mask,
+                    whispers: Whispers::load(),
                     ..default()
                 },
                 start_state: state::StartState::new(kappas),
@@ -194,12 +224,20 @@ impl AppState {
             runtime: Runtime {
                 helix,
                 chatters_update: UserListUpdater::create(),
+                channel_assets: ChannelAssetsLoader::create(repaint.clone()),
+                streams: StreamPoller::create(repaint.clone()),
+                moderation: Moderation::default(),
                 fetch: FetchQueue::create(repaint),
                 helix_ready: helix_tx,
+                tokens: TokenManager::new(&endpoints.id),
+                sent: VecDeque::new(),
                 global_badges: Promise::spawn_thread("global_badges", {
                     move || {
                         let helix = helix_rx.recv().unwrap();
-                        helix.get_badges().expect("get global badges")
+                        helix.get_badges().unwrap_or_else(|err| {
+                            log::error!("cannot get global badges: {err}");
+                            vec![]
+                        })
                     }
                 }),
             },
@@ -220,6 +258,12 @@ pub struct PersistState {
     pub env_config: EnvConfig,
     pub key_mapping: KeyMapping,
     pub channels: Vec<Channel>,
+    #[serde(default)]
+    pub ignored_users: BTreeSet<String>,
+    #[serde(default)]
+    pub highlights: HighlightRules,
+    #[serde(default)]
+    pub filters: MessageFilters,
     pub pixels_per_point: f32,
     pub tab_bar_position: Position,
     pub tab_bar_image_size: f32,
@@ -231,6 +275,9 @@ pub struct BorrowedPersistState<'a> {
     pub env_config: &'a EnvConfig,
     pub key_mapping: &'a KeyMapping,
     pub channels: &'a Vec<Channel>,
+    pub ignored_users: &'a BTreeSet<String>,
+    pub highlights: &'a HighlightRules,
+    pub filters: &'a MessageFilters,
     pub pixels_per_point: &'a f32,
     pub tab_bar_position: Position,
     pub tab_bar_image_size: f32,
@@ -211,3 +211,191 @@ where
         Ok(StoredImage { image, data })
     }
 }
+
+pub struct CacheStore {
+    conn: rusqlite::Connection,
+}
+
+impl CacheStore {
+    // TODO get this at runtime
+    const DB_NAME: &'static str = "cache.db";
+
+    const SCHEMA: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS cache (
+            key        STRING NOT NULL UNIQUE,
+            fetched_at INTEGER NOT NULL,
+            data       STRING NOT NULL
+        );
+        "#;
+
+    fn open() -> Self {
+        let conn = rusqlite::Connection::open(Self::DB_NAME).expect("open connection");
+        conn.execute(Self::SCHEMA, []).expect("create table");
+        Self { conn }
+    }
+
+    fn now() -> i64 {
+        time::OffsetDateTime::now_utc().unix_timestamp()
+    }
+
+    pub fn put<T>(key: &str, item: &T) -> bool
+    where
+        T: serde::Serialize,
+    {
+        let res = Self::open().conn.execute(
+            r#"
+                INSERT OR REPLACE INTO cache (key, fetched_at, data)
+                    VALUES (:key, :fetched_at, :data);
+                "#,
+            rusqlite::named_params! {
+                ":key": key,
+                ":fetched_at": Self::now(),
+                ":data": serde_json::to_string(item).expect("valid json"),
+            },
+        );
+
+        matches!(res, Ok(1))
+    }
+
+    /// Gets the item for `key` if it was stored less than `ttl` ago
+    pub fn get<T>(key: &str, ttl: std::time::Duration) -> Option<T>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let (fetched_at, data) = Self::open()
+            .conn
+            .query_row(
+                "SELECT fetched_at, data FROM cache WHERE key = :key;",
+                rusqlite::named_params! {
+                    ":key": key,
+                },
+                |row| {
+                    Ok((
+                        row.get::<_, i64>("fetched_at")?,
+                        row.get::<_, String>("data")?,
+                    ))
+                },
+            )
+            .ok()?;
+
+        if Self::now().saturating_sub(fetched_at) > ttl.as_secs() as i64 {
+            return None;
+        }
+
+        serde_json::from_str(&data).ok()
+    }
+
+    pub fn remove(key: &str) -> bool {
+        let res = Self::open().conn.execute(
+            "DELETE FROM cache WHERE key = :key;",
+            rusqlite::named_params! {
+                ":key": key,
+            },
+        );
+
+        matches!(res, Ok(1))
+    }
+}
+
+/// Things that happened in a channel, kept so they can be reviewed later
+pub struct EventStore {
+    conn: rusqlite::Connection,
+}
+
+impl EventStore {
+    // TODO get this at runtime
+    const DB_NAME: &'static str = "events.db";
+
+    const SCHEMA: &'static str = r#"
+        CREATE TABLE IF NOT EXISTS events (
+            kind    STRING NOT NULL,
+            channel STRING NOT NULL,
+            at      INTEGER NOT NULL,
+            data    STRING NOT NULL
+        );
+        "#;
+
+    fn open() -> Self {
+        let conn = rusqlite::Connection::open(Self::DB_NAME).expect("open connection");
+        conn.execute(Self::SCHEMA, []).expect("create table");
+        Self { conn }
+    }
+
+    pub fn add<T>(kind: &str, channel: &str, at: i64, item: &T) -> bool
+    where
+        T: serde::Serialize,
+    {
+        let res = Self::open().conn.execute(
+            r#"
+                INSERT INTO events (kind, channel, at, data)
+                    VALUES (:kind, :channel, :at, :data);
+                "#,
+            rusqlite::named_params! {
+                ":kind": kind,
+                ":channel": channel,
+                ":at": at,
+                ":data": serde_json::to_string(item).expect("valid json"),
+            },
+        );
+
+        matches!(res, Ok(1))
+    }
+
+    /// Gets the events of `kind` in the channel since the unix timestamp, oldest first
+    pub fn since<T>(kind: &str, channel: &str, since: i64) -> Vec<(i64, T)>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let conn = Self::open().conn;
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT at, data FROM events
+                    WHERE kind = :kind AND channel = :channel AND at >= :since
+                    ORDER BY at ASC;
+                "#,
+            )
+            .expect("valid sql");
+
+        let iter = stmt.query_map(
+            rusqlite::named_params! {
+                ":kind": kind,
+                ":channel": channel,
+                ":since": since,
+            },
+            |row| Ok((row.get::<_, i64>("at")?, row.get::<_, String>("data")?)),
+        );
+
+        let iter = match iter {
+            Ok(iter) => iter,
+            Err(_) => return vec![],
+        };
+
+        iter.flatten()
+            .filter_map(|(at, data)| Some((at, serde_json::from_str(&data).ok()?)))
+            .collect()
+    }
+
+    /// Gets every channel with events of `kind`
+    pub fn channels(kind: &str) -> Vec<String> {
+        let conn = Self::open().conn;
+        let mut stmt = conn
+            .prepare(
+                r#"
+                SELECT DISTINCT channel FROM events
+                    WHERE kind = :kind
+                    ORDER BY channel ASC;
+                "#,
+            )
+            .expect("valid sql");
+
+        let iter = stmt.query_map(rusqlite::named_params! { ":kind": kind }, |row| {
+            row.get::<_, String>("channel")
+        });
+
+        match iter {
+            Ok(iter) => iter.flatten().collect(),
+            Err(_) => vec![],
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::{helix::Stream, store::EventStore};
+
+/// A change in a channel's stream
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Live { title: String, game: String },
+    Offline,
+    Title { title: String },
+    Game { game: String },
+}
+
+impl StreamEvent {
+    const KIND: &'static str = "stream";
+
+    /// What changed between the two polls
+    pub fn diff(old: Option<&Stream>, new: Option<&Stream>) -> Vec<Self> {
+        match (old, new) {
+            (None, Some(new)) => vec![Self::Live {
+                title: new.title.clone(),
+                game: new.game_name.clone(),
+            }],
+            (Some(..), None) => vec![Self::Offline],
+            (Some(old), Some(new)) => Self::changes(&old.title, &old.game_name, new),
+            (None, None) => vec![],
+        }
+    }
+
+    /// What changed since the events we stored, for the first poll after joining
+    pub fn diff_stored(channel: &str, new: Option<&Stream>) -> Vec<Self> {
+        // there are only a few of these a day, so going through all of them is cheap
+        let stored = EventStore::since::<Self>(Self::KIND, Self::key(channel), 0);
+        let last = Self::replay(stored.into_iter().map(|(_, event)| event));
+        Self::diff_last(last, new)
+    }
+
+    /// The title and category the events left the stream with, if it was live
+    fn replay(events: impl IntoIterator<Item = Self>) -> Option<(String, String)> {
+        events.into_iter().fold(None, |stream, event| match event {
+            Self::Live { title, game } => Some((title, game)),
+            Self::Offline => None,
+            Self::Title { title } => stream.map(|(_, game)| (title, game)),
+            Self::Game { game } => stream.map(|(title, _)| (title, game)),
+        })
+    }
+
+    fn diff_last(last: Option<(String, String)>, new: Option<&Stream>) -> Vec<Self> {
+        match (last, new) {
+            (None, Some(..)) => Self::diff(None, new),
+            (Some(..), None) => vec![Self::Offline],
+            (Some((title, game)), Some(new)) => Self::changes(&title, &game, new),
+            (None, None) => vec![],
+        }
+    }
+
+    fn changes(title: &str, game: &str, new: &Stream) -> Vec<Self> {
+        let mut events = vec![];
+        if title != new.title {
+            events.push(Self::Title {
+                title: new.title.clone(),
+            })
+        }
+        if game != new.game_name {
+            events.push(Self::Game {
+                game: new.game_name.clone(),
+            })
+        }
+        events
+    }
+
+    pub fn store(&self, channel: &str, at: i64) -> bool {
+        EventStore::add(Self::KIND, Self::key(channel), at, self)
+    }
+
+    pub fn since(channel: &str, since: i64) -> Vec<(i64, Self)> {
+        EventStore::since(Self::KIND, Self::key(channel), since)
+    }
+
+    fn key(channel: &str) -> &str {
+        channel.strip_prefix('#').unwrap_or(channel)
+    }
+}
+
+impl std::fmt::Display for StreamEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Live { title, game } if game.is_empty() => write!(f, "went live: {title}"),
+            Self::Live { title, game } => write!(f, "went live with {game}: {title}"),
+            Self::Offline => f.write_str("went offline"),
+            Self::Title { title } => write!(f, "changed the title to: {title}"),
+            Self::Game { game } => write!(f, "changed the category to: {game}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(title: &str, game: &str) -> Stream {
+        Stream {
+            id: "1".into(),
+            user_id: "2".into(),
+            user_login: "c".into(),
+            user_name: "c".into(),
+            game_name: game.into(),
+            ty: "live".into(),
+            title: title.into(),
+            viewer_count: 0,
+            started_at: time::OffsetDateTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn diff_stored() {
+        let live = |title: &str, game: &str| StreamEvent::Live {
+            title: title.into(),
+            game: game.into(),
+        };
+
+        let last = StreamEvent::replay([
+            live("old", "Chatting"),
+            StreamEvent::Offline,
+            live("hello", "Chatting"),
+            StreamEvent::Title { title: "hi".into() },
+        ]);
+        assert_eq!(last, Some(("hi".into(), "Chatting".into())));
+
+        // the title and category changed while we were away
+        assert_eq!(
+            StreamEvent::diff_last(last.clone(), Some(&stream("bye", "Art"))),
+            [
+                StreamEvent::Title {
+                    title: "bye".into()
+                },
+                StreamEvent::Game { game: "Art".into() },
+            ]
+        );
+        assert_eq!(
+            StreamEvent::diff_last(last.clone(), Some(&stream("hi", "Chatting"))),
+            []
+        );
+        assert_eq!(StreamEvent::diff_last(last, None), [StreamEvent::Offline]);
+
+        let last = StreamEvent::replay([live("hello", "Chatting"), StreamEvent::Offline]);
+        assert_eq!(last, None);
+        assert_eq!(
+            StreamEvent::diff_last(last, Some(&stream("hi", "Art"))),
+            [live("hi", "Art")]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::{collections::HashSet, time::Duration};
+
+use flume::{Receiver, RecvTimeoutError, Sender};
+
+use crate::{
+    helix::{Client, Stream},
+    RequestPaint,
+};
+
+/// Polls whether the joined channels are live
+pub struct StreamPoller {
+    sub: Sender<Action>,
+    receiver: Receiver<(String, Option<Stream>)>,
+    has_client: bool,
+}
+
+impl StreamPoller {
+    pub fn create(repaint: impl RequestPaint + 'static) -> Self {
+        let (sub, subscribe) = flume::unbounded();
+        let (sender, receiver) = flume::unbounded();
+
+        std::thread::spawn(move || {
+            StreamPollerInner {
+                subscribe,
+                sender,
+                client: None,
+                set: HashSet::new(),
+            }
+            .run(repaint)
+        });
+
+        Self {
+            sub,
+            receiver,
+            has_client: false,
+        }
+    }
+
+    /// Gives the poller a client, this does nothing once it has one
+    pub fn set_client(&mut self, helix: &Client) {
+        if std::mem::replace(&mut self.has_client, true) {
+            return;
+        }
+        let _ = self.sub.send(Action::Client(helix.clone()));
+    }
+
+    pub fn poll(&self) -> Vec<(String, Option<Stream>)> {
+        self.receiver.try_iter().collect()
+    }
+
+    pub fn subscribe(&mut self, channel: &str) {
+        let channel = channel.strip_prefix('#').unwrap_or(channel);
+        let _ = self.sub.send(Action::Add(channel.to_string()));
+    }
+
+    pub fn unsubscribe(&mut self, channel: &str) {
+        let channel = channel.strip_prefix('#').unwrap_or(channel);
+        let _ = self.sub.send(Action::Remove(channel.to_string()));
+    }
+}
+
+enum Action {
+    Add(String),
+    Remove(String),
+    Client(Client),
+}
+
+struct StreamPollerInner {
+    subscribe: Receiver<Action>,
+    sender: Sender<(String, Option<Stream>)>,
+    client: Option<Client>,
+    set: HashSet<String>,
+}
+
+impl StreamPollerInner {
+    const INTERVAL: Duration = Duration::from_secs(60);
+
+    fn run(mut self, repaint: impl RequestPaint) {
+        let mut last = std::time::Instant::now();
+
+        loop {
+            let channels = match self.subscribe.recv_timeout(Self::INTERVAL) {
+                Ok(Action::Add(channel)) => {
+                    self.set.insert(channel.clone());
+                    vec![channel]
+                }
+                Ok(Action::Remove(channel)) => {
+                    self.set.remove(&channel);
+                    continue;
+                }
+                Ok(Action::Client(client)) => {
+                    self.client.replace(client);
+                    self.set.iter().cloned().collect()
+                }
+                Err(RecvTimeoutError::Timeout) => vec![],
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            let channels = if last.elapsed() >= Self::INTERVAL {
+                last = std::time::Instant::now();
+                self.set.iter().cloned().collect()
+            } else {
+                channels
+            };
+
+            if self.fetch(&channels) {
+                repaint.request_repaint();
+            }
+        }
+    }
+
+    fn fetch(&self, channels: &[String]) -> bool {
+        let client = match &self.client {
+            Some(client) if !channels.is_empty() => client,
+            _ => return false,
+        };
+
+        let streams = match client.get_streams(channels.iter().map(|s| &**s)) {
+            Ok(streams) => streams,
+            Err(err) => {
+                log::warn!("cannot get streams: {err}");
+                return false;
+            }
+        };
+
+        for channel in channels {
+            let stream = streams
+                .iter()
+                .find(|stream| stream.user_login.eq_ignore_ascii_case(channel))
+                .cloned();
+
+            if self.sender.send((channel.clone(), stream)).is_err() {
+                return false;
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::{Duration, Instant};
+
+use poll_promise::Promise;
+
+use crate::login::{DeviceFlow, UserToken};
+
+#[derive(Debug, Clone, serde::Deserialize)]
+pub struct TokenInfo {
+    pub client_id: String,
+    pub login: String,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub user_id: String,
+    pub expires_in: u64,
+
+    #[serde(skip, default = "Instant::now")]
+    pub validated_at: Instant,
+}
+
+impl TokenInfo {
+    /// Asks Twitch whether the `token` is still valid
+    ///
+    /// This accepts the `oauth:` prefixed form used for IRC
+    pub fn validate(agent: &ureq::Agent, base: &str, token: &str) -> anyhow::Result<Self> {
+        let token = token.strip_prefix("oauth:").unwrap_or(token);
+        anyhow::ensure!(!token.is_empty(), "the token was empty");
+
+        match agent
+            .get(&format!("{base}/validate"))
+            .set("authorization", &format!("OAuth {token}"))
+            .call()
+        {
+            Ok(resp) => Ok(resp.into_json()?),
+            Err(ureq::Error::Status(401, _)) => {
+                anyhow::bail!("the token is invalid or has expired")
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// How long until the token expires
+    ///
+    /// Tokens that never expire report `None`
+    pub fn expires_in(&self) -> Option<Duration> {
+        if self.expires_in == 0 {
+            return None;
+        }
+
+        Some(Duration::from_secs(self.expires_in).saturating_sub(self.validated_at.elapsed()))
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+pub struct TokenManager {
+    base: String,
+    token: String,
+    validation: Option<Promise<Result<TokenInfo, String>>>,
+    last: Option<Instant>,
+    refreshing: Option<Promise<Result<UserToken, String>>>,
+    // the token we last tried to refresh, so a bad refresh token is only tried once
+    refreshed: String,
+}
+
+impl TokenManager {
+    // twitch asks that tokens are validated hourly
+    const REVALIDATE: Duration = Duration::from_secs(60 * 60);
+
+    pub fn new(base: impl ToString) -> Self {
+        Self {
+            base: base.to_string(),
+            token: String::new(),
+            validation: None,
+            last: None,
+            refreshing: None,
+            refreshed: String::new(),
+        }
+    }
+
+    /// The base url of the id service
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    pub fn validate(&mut self, token: &str) {
+        self.token = token.to_string();
+        self.last.replace(Instant::now());
+
+        let (base, token) = (self.base.clone(), self.token.clone());
+        self.validation
+            .replace(Promise::spawn_thread("validate_token", move || {
+                let info = TokenInfo::validate(&ureq::agent(), &base, &token)
+                    .map_err(|err| err.to_string());
+                match &info {
+                    Ok(info) => log::debug!("validated token for: {}", info.login),
+                    Err(err) => log::warn!("cannot validate token: {err}"),
+                }
+                info
+            }));
+    }
+
+    /// Validates the token if it hasn't been yet, or if it's been a while
+    pub fn poll(&mut self, token: &str) {
+        if token.is_empty() || self.is_validating() {
+            return;
+        }
+
+        if self
+            .last
+            .filter(|d| d.elapsed() < Self::REVALIDATE)
+            .is_none()
+        {
+            self.validate(token)
+        }
+    }
+
+    /// Whether the last validation was for a different token
+    pub fn is_stale(&self, token: &str) -> bool {
+        self.validation.is_some() && self.token != token
+    }
+
+    pub fn is_validating(&self) -> bool {
+        matches!(&self.validation, Some(promise) if promise.ready().is_none())
+    }
+
+    /// Gets a new token if the current one failed to validate
+    pub fn try_refresh(&mut self, client_id: &str, refresh_token: &str) {
+        if refresh_token.is_empty() || self.refreshing.is_some() || self.refreshed == self.token {
+            return;
+        }
+        if !matches!(self.status(), Some(Err(..))) {
+            return;
+        }
+
+        self.refreshed = self.token.clone();
+        let (base, client_id, refresh_token) = (
+            self.base.clone(),
+            client_id.to_string(),
+            refresh_token.to_string(),
+        );
+        self.refreshing
+            .replace(Promise::spawn_thread("refresh_token", move || {
+                DeviceFlow::new(ureq::agent(), &base, &client_id)
+                    .refresh(&refresh_token)
+                    .map_err(|err| err.to_string())
+            }));
+    }
+
+    /// Returns the new token once a refresh has finished
+    pub fn poll_refresh(&mut self) -> Option<Result<UserToken, String>> {
+        self.refreshing.as_ref()?.ready()?;
+        self.refreshing.take().map(Promise::block_and_take)
+    }
+
+    pub fn status(&self) -> Option<&Result<TokenInfo, String>> {
+        self.validation.as_ref()?.ready()
+    }
+
+    pub fn info(&self) -> Option<&TokenInfo> {
+        self.status()?.as_ref().ok()
+    }
+}
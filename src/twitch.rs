@@ -8,6 +8,8 @@ use std::{
 use anyhow::Context;
 use flume::Receiver;
 
+use crate::helix::Kind;
+
 pub const TWITCH_COLORS: [Color; 15] = [
     Color(0x00, 0x00, 0xFF), //
     Color(0x8A, 0x2B, 0xE2), //
@@ -75,29 +77,7 @@ impl Client {
         loop {
             let msg = self.read_line()?;
             if let Command::Ready = msg.command {
-                let user_name = msg
-                    .tags
-                    .get("display-name")
-                    .map(ToString::to_string)
-                    .with_context(|| "missing display-name")?;
-
-                let user_id = msg
-                    .tags
-                    .get_parsed("user-id")
-                    .with_context(|| "missing user-id")??;
-
-                let color = msg
-                    .tags
-                    .get_parsed("color")
-                    .with_context(|| "missing color")??;
-
-                let identity = Identity {
-                    user_name,
-                    user_id,
-                    color,
-                };
-
-                return Ok(identity);
+                return Identity::from_tags(&msg.tags);
             }
         }
     }
@@ -136,7 +116,7 @@ impl Client {
                 let msg = this.read_line()?;
                 repaint.request_repaint();
 
-                if let Err(..) = tx.send(msg) {
+                if tx.send(msg).is_err() {
                     break;
                 }
             }
@@ -232,6 +212,86 @@ pub struct Identity {
     pub user_name: String,
     pub user_id: i64,
     pub color: Color,
+    /// Our global badges
+    pub badges: Vec<(String, String)>,
+    pub emote_sets: Vec<String>,
+}
+
+impl Identity {
+    fn from_tags(tags: &Tags) -> anyhow::Result<Self> {
+        let user_name = tags
+            .get("display-name")
+            .map(ToString::to_string)
+            .with_context(|| "missing display-name")?;
+
+        let user_id = tags
+            .get_parsed("user-id")
+            .with_context(|| "missing user-id")??;
+
+        let color = tags
+            .get_parsed("color")
+            .with_context(|| "missing color")??;
+
+        Ok(Self {
+            user_name,
+            user_id,
+            color,
+            badges: tags.badges().collect(),
+            emote_sets: tags.emote_sets(),
+        })
+    }
+}
+
+/// Who we are in a channel, from USERSTATE
+#[derive(Debug, Clone)]
+pub struct ChannelIdentity {
+    pub display_name: String,
+    pub color: Option<Color>,
+    pub badges: Vec<(String, String)>,
+    pub emote_sets: Vec<String>,
+    pub kind: Kind,
+}
+
+impl ChannelIdentity {
+    fn from_tags(tags: &Tags) -> Self {
+        let badges = tags.badges().collect::<Vec<_>>();
+
+        let kind = badges
+            .iter()
+            .filter_map(|(set_id, _)| Kind::parse(set_id))
+            .min()
+            .unwrap_or(Kind::Viewer);
+
+        // the badge can be missing for a moderator that hid it
+        let kind = match tags.get("mod") {
+            Some("1") => kind.min(Kind::Moderator),
+            _ => kind,
+        };
+
+        Self {
+            display_name: tags.get("display-name").unwrap_or_default().to_string(),
+            color: tags.get_parsed("color").transpose().ok().flatten(),
+            badges,
+            emote_sets: tags.emote_sets(),
+            kind,
+        }
+    }
+
+    pub const fn is_moderator(&self) -> bool {
+        matches!(self.kind, Kind::Broadcaster | Kind::Moderator)
+    }
+
+    /// Whether we can use the emote here
+    pub fn can_use(&self, emote: &crate::helix::Emotes) -> bool {
+        emote.emote_set_id.is_empty() || self.emote_sets.contains(&emote.emote_set_id)
+    }
+}
+
+/// Our state in a channel, sent when we join it and after we send a message
+#[derive(Debug)]
+pub struct UserState<'a> {
+    pub channel: &'a str,
+    pub identity: ChannelIdentity,
 }
 
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
@@ -261,6 +321,45 @@ impl Tags {
         self.inner.get(key).map(|s| &**s)
     }
 
+    fn badges(&self) -> impl Iterator<Item = (String, String)> + '_ {
+        self.get("badges")
+            .into_iter()
+            .flat_map(|c| c.split(','))
+            .flat_map(|c| c.split_once('/'))
+            .map(|(set_id, id)| (set_id.to_string(), id.to_string()))
+    }
+
+    fn emote_sets(&self) -> Vec<String> {
+        self.get("emote-sets")
+            .into_iter()
+            .flat_map(|c| c.split(','))
+            .filter(|c| !c.is_empty())
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    /// Gets the value with the IRCv3 tag escapes undone
+    pub fn get_unescaped(&self, key: &str) -> Option<String> {
+        let value = self.get(key)?;
+        let mut out = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some(':') => out.push(';'),
+                Some('s') => out.push(' '),
+                Some('r') => out.push('\r'),
+                Some('n') => out.push('\n'),
+                Some(ch) => out.push(ch),
+                None => {}
+            }
+        }
+        Some(out)
+    }
+
     pub fn get_parsed<T>(&self, key: &str) -> Option<anyhow::Result<T>>
     where
         T: FromStr,
@@ -279,6 +378,15 @@ pub enum Command {
     Join,
     Part,
     Privmsg,
+    Names,
+    EndOfNames,
+    UserNotice,
+    ClearChat,
+    ClearMsg,
+    RoomState,
+    UserState,
+    Whisper,
+    Notice,
     Error,
     Other,
 }
@@ -293,6 +401,15 @@ impl Command {
             "JOIN" => Self::Join,
             "PART" => Self::Part,
             "PRIVMSG" => Self::Privmsg,
+            "353" => Self::Names,
+            "366" => Self::EndOfNames,
+            "USERNOTICE" => Self::UserNotice,
+            "CLEARCHAT" => Self::ClearChat,
+            "CLEARMSG" => Self::ClearMsg,
+            "ROOMSTATE" => Self::RoomState,
+            "USERSTATE" => Self::UserState,
+            "WHISPER" => Self::Whisper,
+            "NOTICE" => Self::Notice,
             "ERROR" => Self::Error,
             _ => Self::Other,
         }
@@ -348,11 +465,221 @@ pub struct Part<'a> {
     pub user: &'a str,
 }
 
+/// A batch of users already in the channel, sent after we join it
+#[derive(Debug)]
+pub struct Names<'a> {
+    pub channel: &'a str,
+    pub users: &'a str,
+}
+
+impl<'a> Names<'a> {
+    pub fn users(&self) -> impl Iterator<Item = &'a str> {
+        self.users.split_ascii_whitespace()
+    }
+}
+
+/// A moderator cleared the whole chat, or timed out or banned a user
+#[derive(Debug)]
+pub struct ClearChat<'a> {
+    pub channel: &'a str,
+    pub user: Option<&'a str>,
+    /// The timeout, in seconds. This is `None` for a ban
+    pub duration: Option<u64>,
+}
+
+/// The chat modes of a room
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct RoomSettings {
+    pub emote_only: bool,
+    /// How many minutes someone must have followed for
+    pub followers_only: Option<u64>,
+    pub r9k: bool,
+    /// How many seconds someone must wait between messages
+    pub slow: Option<u64>,
+    pub subs_only: bool,
+}
+
+/// A change to the chat modes of a room
+///
+/// Twitch sends every mode when we join, but only the changed one afterwards
+#[derive(Debug)]
+pub struct RoomState<'a> {
+    pub channel: &'a str,
+    pub tags: &'a Tags,
+}
+
+impl<'a> RoomState<'a> {
+    pub fn apply(&self, settings: &mut RoomSettings) {
+        let flag = |key| self.tags.get(key).map(|s| s == "1");
+        // '-1' for followers-only and '0' for slow mean the mode is off
+        let number = |key, off: &str| {
+            self.tags
+                .get(key)
+                .map(|s| (s != off).then(|| s.parse().ok()).flatten())
+        };
+
+        if let Some(emote_only) = flag("emote-only") {
+            settings.emote_only = emote_only
+        }
+        if let Some(followers_only) = number("followers-only", "-1") {
+            settings.followers_only = followers_only
+        }
+        if let Some(r9k) = flag("r9k") {
+            settings.r9k = r9k
+        }
+        if let Some(slow) = number("slow", "0") {
+            settings.slow = slow
+        }
+        if let Some(subs_only) = flag("subs-only") {
+            settings.subs_only = subs_only
+        }
+    }
+}
+
+/// A message from Twitch, like the result of a chat command
+#[derive(Debug)]
+pub struct Notice<'a> {
+    pub channel: &'a str,
+    pub msg_id: Option<&'a str>,
+    pub data: &'a str,
+}
+
+/// A private message sent to us
+#[derive(Debug)]
+pub struct Whisper<'a> {
+    pub from: &'a str,
+    pub to: &'a str,
+    pub data: &'a str,
+    pub tags: &'a Tags,
+}
+
+impl<'a> Whisper<'a> {
+    pub fn color(&self) -> Option<Color> {
+        self.tags.get_parsed("color").transpose().ok().flatten()
+    }
+}
+
+/// A moderator deleted a single message
+#[derive(Debug)]
+pub struct ClearMsg<'a> {
+    pub channel: &'a str,
+    pub login: Option<&'a str>,
+    pub target: uuid::Uuid,
+    pub data: Option<&'a str>,
+}
+
+/// The kind of a USERNOTICE, from its `msg-id` and `msg-param-*` tags
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum UserNoticeKind {
+    Sub {
+        months: u64,
+        plan: String,
+    },
+    Resub {
+        months: u64,
+        streak: Option<u64>,
+        plan: String,
+    },
+    SubGift {
+        recipient: String,
+        months: u64,
+        plan: String,
+    },
+    SubMysteryGift {
+        count: u64,
+        plan: String,
+    },
+    Raid {
+        from: String,
+        viewers: u64,
+    },
+    Announcement {
+        color: String,
+    },
+    BitsBadgeTier {
+        threshold: u64,
+    },
+    Ritual {
+        name: String,
+    },
+    Other(String),
+}
+
+impl UserNoticeKind {
+    fn parse(tags: &Tags) -> Option<Self> {
+        let param = |key: &str| {
+            tags.get_unescaped(&format!("msg-param-{key}"))
+                .unwrap_or_default()
+        };
+        let number = |key: &str| param(key).parse::<u64>().unwrap_or_default();
+
+        Some(match tags.get("msg-id")? {
+            "sub" => Self::Sub {
+                months: number("cumulative-months"),
+                plan: param("sub-plan"),
+            },
+            "resub" => Self::Resub {
+                months: number("cumulative-months"),
+                streak: (param("should-share-streak") == "1").then(|| number("streak-months")),
+                plan: param("sub-plan"),
+            },
+            "subgift" => Self::SubGift {
+                recipient: param("recipient-display-name"),
+                months: number("months"),
+                plan: param("sub-plan"),
+            },
+            "submysterygift" => Self::SubMysteryGift {
+                count: number("mass-gift-count"),
+                plan: param("sub-plan"),
+            },
+            "raid" => Self::Raid {
+                from: param("displayName"),
+                viewers: number("viewerCount"),
+            },
+            "announcement" => Self::Announcement {
+                color: param("color"),
+            },
+            "bitsbadgetier" => Self::BitsBadgeTier {
+                threshold: number("threshold"),
+            },
+            "ritual" => Self::Ritual {
+                name: param("ritual-name"),
+            },
+            other => Self::Other(other.to_string()),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct UserNotice<'a> {
+    pub channel: &'a str,
+    pub kind: UserNoticeKind,
+    /// The unescaped message Twitch made for this notice
+    pub system_msg: String,
+    pub data: Option<&'a str>,
+    pub tags: &'a Tags,
+}
+
+impl<'a> UserNotice<'a> {
+    /// The message the user attached to the notice, if they did
+    pub fn message(&self) -> Option<Privmsg<'a>> {
+        Some(Privmsg {
+            target: self.channel,
+            sender: self.tags.get("login")?,
+            data: self.data?,
+            action: false,
+            tags: self.tags,
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct Privmsg<'a> {
     pub target: &'a str,
     pub sender: &'a str,
     pub data: &'a str,
+    /// Whether this was sent with `/me`, `data` doesn't have the CTCP wrapping
+    pub action: bool,
     pub tags: &'a Tags,
 }
 
@@ -391,7 +718,10 @@ impl<'a> Privmsg<'a> {
         }
     }
 
-    pub fn make_spans(&self) -> (uuid::Uuid, Vec<EmoteSpan>) {
+    /// Splits the message into text, emotes and cheers
+    ///
+    /// Only words whose prefix passes `is_cheermote` are cheers, and only if bits were sent
+    pub fn make_spans(&self, is_cheermote: impl Fn(&str) -> bool) -> (uuid::Uuid, Vec<EmoteSpan>) {
         let id = self.id();
 
         let chars = self.data.trim_end().chars().collect::<Vec<_>>();
@@ -425,10 +755,58 @@ impl<'a> Privmsg<'a> {
             spans.push(EmoteSpan::Text(trim_spaces(&chars[cursor..])))
         }
 
+        if self.bits().is_some() {
+            spans = spans
+                .into_iter()
+                .flat_map(|span| Self::split_cheers(span, &is_cheermote))
+                .collect();
+        }
+
         (id, spans)
     }
 
-    pub fn badges(&self) -> impl Iterator<Item = (&str, &str)> {
+    /// The total amount of bits cheered with this message
+    pub fn bits(&self) -> Option<u64> {
+        self.tags.get_parsed("bits").transpose().ok().flatten()
+    }
+
+    // cheers are just words like 'Cheer100' in the text, so split them out
+    fn split_cheers(span: EmoteSpan, is_cheermote: impl Fn(&str) -> bool) -> Vec<EmoteSpan> {
+        let text = match span {
+            EmoteSpan::Text(text) => text,
+            span => return vec![span],
+        };
+
+        let mut spans = vec![];
+        let mut words = Vec::<&str>::new();
+        for word in text.split(' ') {
+            match EmoteSpan::parse_cheer(word, &is_cheermote) {
+                Some(cheer) => {
+                    if !words.is_empty() {
+                        spans.push(EmoteSpan::Text(words.join(" ")));
+                        words.clear();
+                    }
+                    spans.push(cheer)
+                }
+                None => words.push(word),
+            }
+        }
+
+        if !words.is_empty() {
+            spans.push(EmoteSpan::Text(words.join(" ")));
+        }
+        spans
+    }
+
+    /// The sender's role in the channel, from their badges
+    pub fn kind(&self) -> Kind {
+        self.badges()
+            .filter_map(|(set_id, _)| Kind::parse(set_id))
+            .min()
+            .unwrap_or(Kind::Viewer)
+    }
+
+    pub fn badges(&self) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
         self.tags
             .get("badges")
             .into_iter()
@@ -457,9 +835,28 @@ impl<'a> Privmsg<'a> {
 pub enum EmoteSpan {
     Emote(String),
     Text(String),
+    Cheer { prefix: String, bits: u64 },
     // TODO hyper link
 }
 
+impl EmoteSpan {
+    fn parse_cheer(word: &str, is_cheermote: impl Fn(&str) -> bool) -> Option<Self> {
+        let pos = word.find(|c: char| c.is_ascii_digit())?;
+        let (prefix, bits) = word.split_at(pos);
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_alphabetic()) {
+            return None;
+        }
+        if !is_cheermote(prefix) {
+            return None;
+        }
+
+        Some(Self::Cheer {
+            prefix: prefix.to_string(),
+            bits: bits.parse().ok().filter(|&bits| bits > 0)?,
+        })
+    }
+}
+
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct Message {
     pub tags: Tags,
@@ -477,7 +874,7 @@ impl Message {
         }
 
         Some(Join {
-            channel: self.args.get(0)?,
+            channel: self.args.first()?,
             user: self.prefix.as_user()?,
         })
     }
@@ -488,20 +885,187 @@ impl Message {
         }
 
         Some(Part {
-            channel: self.args.get(0)?,
+            channel: self.args.first()?,
             user: self.prefix.as_user()?,
         })
     }
 
+    pub fn as_names(&self) -> Option<Names<'_>> {
+        if !matches!(self.command, Command::Names) {
+            return None;
+        }
+
+        // :tmi.twitch.tv 353 <nick> = <channel> :<users>
+        Some(Names {
+            channel: self.args.get(2)?,
+            users: self.data.as_deref().unwrap_or_default(),
+        })
+    }
+
+    /// The channel whose names list just finished
+    pub fn as_end_of_names(&self) -> Option<&str> {
+        if !matches!(self.command, Command::EndOfNames) {
+            return None;
+        }
+
+        // :tmi.twitch.tv 366 <nick> <channel> :End of /NAMES list
+        self.args.get(1).map(|s| &**s)
+    }
+
+    pub fn as_user_notice(&self) -> Option<UserNotice<'_>> {
+        if !matches!(self.command, Command::UserNotice) {
+            return None;
+        }
+
+        Some(UserNotice {
+            channel: self.args.first()?,
+            kind: UserNoticeKind::parse(&self.tags)?,
+            system_msg: self.tags.get_unescaped("system-msg").unwrap_or_default(),
+            data: self.data.as_deref(),
+            tags: &self.tags,
+        })
+    }
+
+    pub fn as_clear_chat(&self) -> Option<ClearChat<'_>> {
+        if !matches!(self.command, Command::ClearChat) {
+            return None;
+        }
+
+        Some(ClearChat {
+            channel: self.args.first()?,
+            user: self.data.as_deref(),
+            duration: self.tags.get_parsed("ban-duration").transpose().ok()?,
+        })
+    }
+
+    /// Our global identity, from a GLOBALUSERSTATE
+    pub fn as_identity(&self) -> Option<Identity> {
+        if !matches!(self.command, Command::Ready) {
+            return None;
+        }
+        Identity::from_tags(&self.tags).ok()
+    }
+
+    pub fn as_user_state(&self) -> Option<UserState<'_>> {
+        if !matches!(self.command, Command::UserState) {
+            return None;
+        }
+
+        Some(UserState {
+            channel: self.args.first()?,
+            identity: ChannelIdentity::from_tags(&self.tags),
+        })
+    }
+
+    /// A message for what we sent, Twitch doesn't echo our messages back to us
+    ///
+    /// `/me` is echoed as an action. Other commands like `/ban` aren't chat lines, so they don't
+    /// get one
+    pub fn local_echo(
+        channel: &str,
+        login: &str,
+        identity: &ChannelIdentity,
+        data: &str,
+    ) -> Option<Self> {
+        let data = match data.strip_prefix("/me ") {
+            Some(action) => format!("\x01ACTION {action}\x01"),
+            None if data.starts_with('/') => return None,
+            None => data.to_string(),
+        };
+
+        let badges = identity
+            .badges
+            .iter()
+            .map(|(set_id, id)| format!("{set_id}/{id}"))
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut inner = HashMap::from([
+            ("badges".to_string(), badges),
+            ("display-name".to_string(), identity.display_name.clone()),
+            ("id".to_string(), uuid::Uuid::new_v4().to_string()),
+        ]);
+        if let Some(Color(r, g, b)) = identity.color {
+            inner.insert("color".to_string(), format!("#{r:02X}{g:02X}{b:02X}"));
+        }
+
+        Some(Self {
+            tags: Tags { inner },
+            prefix: Prefix::User {
+                name: login.to_string(),
+            },
+            command: Command::Privmsg,
+            args: vec![channel.to_string()],
+            raw: format!("PRIVMSG {channel} :{data}"),
+            data: Some(data),
+        })
+    }
+
+    pub fn as_notice(&self) -> Option<Notice<'_>> {
+        if !matches!(self.command, Command::Notice) {
+            return None;
+        }
+
+        Some(Notice {
+            channel: self.args.first()?,
+            msg_id: self.tags.get("msg-id"),
+            data: self.data.as_deref()?,
+        })
+    }
+
+    pub fn as_whisper(&self) -> Option<Whisper<'_>> {
+        if !matches!(self.command, Command::Whisper) {
+            return None;
+        }
+
+        Some(Whisper {
+            from: self.prefix.as_user()?,
+            to: self.args.first()?,
+            data: self.data.as_deref()?,
+            tags: &self.tags,
+        })
+    }
+
+    pub fn as_room_state(&self) -> Option<RoomState<'_>> {
+        if !matches!(self.command, Command::RoomState) {
+            return None;
+        }
+
+        Some(RoomState {
+            channel: self.args.first()?,
+            tags: &self.tags,
+        })
+    }
+
+    pub fn as_clear_msg(&self) -> Option<ClearMsg<'_>> {
+        if !matches!(self.command, Command::ClearMsg) {
+            return None;
+        }
+
+        Some(ClearMsg {
+            channel: self.args.first()?,
+            login: self.tags.get("login"),
+            target: self.tags.get_parsed("target-msg-id").transpose().ok()??,
+            data: self.data.as_deref(),
+        })
+    }
+
     pub fn as_privmsg(&self) -> Option<Privmsg<'_>> {
         if !matches!(self.command, Command::Privmsg) {
             return None;
         }
 
+        let data = self.data.as_deref()?;
+        let (data, action) = match data.strip_prefix("\x01ACTION ") {
+            Some(data) => (data.strip_suffix('\x01').unwrap_or(data), true),
+            None => (data, false),
+        };
+
         Some(Privmsg {
-            target: self.args.get(0)?,
+            target: self.args.first()?,
             sender: self.prefix.as_user()?,
-            data: self.data.as_deref()?,
+            data,
+            action,
             tags: &self.tags,
         })
     }
@@ -540,3 +1104,57 @@ impl Message {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "id=f5b1e6a2-3f0c-4e4b-9a52-6f1d2c9b8a11";
+
+    fn cheers(raw: &str) -> Vec<(String, u64)> {
+        let msg = Message::parse(raw).unwrap();
+        let (_, spans) = msg
+            .as_privmsg()
+            .unwrap()
+            .make_spans(|prefix| prefix.eq_ignore_ascii_case("cheer"));
+        spans
+            .into_iter()
+            .filter_map(|span| match span {
+                EmoteSpan::Cheer { prefix, bits } => Some((prefix, bits)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_cheer() {
+        assert_eq!(
+            cheers(&format!(
+                "@bits=50;{ID} :a!a@a.tmi.twitch.tv PRIVMSG #c :Cheer25 hi Kappa10 cheer25"
+            )),
+            [("Cheer".to_string(), 25), ("cheer".to_string(), 25)]
+        );
+        // without bits it's just a word
+        assert!(cheers(&format!(
+            "@{ID} :a!a@a.tmi.twitch.tv PRIVMSG #c :Cheer25 hi"
+        ))
+        .is_empty());
+    }
+
+    #[test]
+    fn local_echo() {
+        let identity = ChannelIdentity::from_tags(&Tags::default());
+        let msg = Message::local_echo("#c", "me", &identity, "hello").unwrap();
+        let pm = msg.as_privmsg().unwrap();
+        assert_eq!((pm.target, pm.sender, pm.data), ("#c", "me", "hello"));
+
+        assert!(!pm.action);
+
+        let msg = Message::local_echo("#c", "me", &identity, "/me waves").unwrap();
+        let pm = msg.as_privmsg().unwrap();
+        assert_eq!((pm.data, pm.action), ("waves", true));
+
+        assert!(Message::local_echo("#c", "me", &identity, "/ban someone").is_none());
+        assert!(Message::local_echo("#c", "me", &identity, "/mewaves").is_none());
+    }
+}
@@ -1,13 +1,27 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use flume::{Receiver, Sender};
 
-use crate::helix::{Chatters, Client};
+use crate::{
+    helix::{Chatters, Client, IdOrLogin},
+    token::TokenInfo,
+};
+
+/// The scope Helix needs to list the chatters
+pub const CHATTERS_SCOPE: &str = "moderator:read:chatters";
+
+/// A user token that can read the chatters
+#[derive(Clone)]
+pub struct Moderator {
+    pub client: Client,
+    pub user_id: String,
+}
 
 pub struct UserListUpdater {
     sub: Sender<Action>,
     wakeup: Sender<()>,
     receiver: Receiver<(String, Chatters)>,
+    authorized: Option<String>,
 }
 
 impl UserListUpdater {
@@ -21,6 +35,9 @@ impl UserListUpdater {
                 subscribe,
                 sender,
                 wakeup,
+                moderator: None,
+                ids: <_>::default(),
+                forbidden: <_>::default(),
                 set: <_>::default(),
             }
             .run()
@@ -30,6 +47,7 @@ impl UserListUpdater {
             sub,
             receiver: rx,
             wakeup: wakeup_tx,
+            authorized: None,
         }
     }
 
@@ -38,6 +56,30 @@ impl UserListUpdater {
         self.receiver.try_iter().collect()
     }
 
+    /// Uses the `token` to list chatters, if it has the right scope
+    ///
+    /// This does nothing if the token hasn't changed since the last call
+    pub fn authorize(&mut self, helix: &Client, info: Option<&TokenInfo>, token: &str) {
+        let info = info.filter(|info| info.has_scope(CHATTERS_SCOPE));
+        let key = info.map(|_| token);
+        if self.authorized.as_deref() == key {
+            return;
+        }
+
+        self.authorized = key.map(ToString::to_string);
+        let moderator = info.map(|info| Moderator {
+            client: helix.for_user(&info.client_id, token),
+            user_id: info.user_id.clone(),
+        });
+
+        match &moderator {
+            Some(..) => log::debug!("listing chatters with helix"),
+            None => log::debug!("listing chatters from irc"),
+        }
+
+        let _ = self.sub.send(Action::Authorize(moderator));
+    }
+
     pub fn request_update(&mut self, channel: impl ToString) {
         let _ = self.sub.send(Action::Update(channel.to_string()));
     }
@@ -55,16 +97,24 @@ enum Action {
     Add(String),
     Remove(String),
     Update(String),
+    Authorize(Option<Moderator>),
 }
 
 struct UserListUpdateInner {
     subscribe: Receiver<Action>,
     sender: Sender<(String, Chatters)>,
     wakeup: Receiver<()>,
+    moderator: Option<Moderator>,
+    ids: HashMap<String, String>,
+    // channels we aren't allowed to list, until the token changes
+    forbidden: HashSet<String>,
     set: HashSet<String>,
 }
 
 impl UserListUpdateInner {
+    // helix is slow to notice chatters anyway, so don't ask too often
+    const REFRESH: std::time::Duration = std::time::Duration::from_secs(30);
+
     fn run(mut self) {
         let mut last = std::time::Instant::now();
 
@@ -84,10 +134,8 @@ impl UserListUpdateInner {
                 _ => {}
             }
 
-            if last.elapsed() > std::time::Duration::from_secs(10) {
-                for channel in &self.set {
-                    self.fetch(channel);
-                }
+            if last.elapsed() > Self::REFRESH {
+                self.fetch_all();
                 last = std::time::Instant::now();
             }
         }
@@ -105,19 +153,62 @@ impl UserListUpdateInner {
             Action::Update(ch) => {
                 self.fetch(&ch);
             }
+            Action::Authorize(moderator) => {
+                self.moderator = moderator;
+                self.forbidden.clear();
+                self.fetch_all();
+            }
+        }
+    }
+
+    fn fetch_all(&mut self) {
+        for channel in self.set.clone() {
+            self.fetch(&channel);
         }
     }
 
-    fn fetch(&self, channel: &str) {
+    fn fetch(&mut self, channel: &str) {
         let channel = channel.strip_prefix('#').unwrap_or(channel);
 
-        match Client::get_chatters_for(channel) {
+        // without a token the list only comes from irc
+        let moderator = match &self.moderator {
+            Some(..) if self.forbidden.contains(channel) => return,
+            Some(moderator) => moderator,
+            None => return,
+        };
+
+        let res = Self::broadcaster_id(&mut self.ids, &moderator.client, channel)
+            .and_then(|id| moderator.client.get_chatters(&id, &moderator.user_id));
+
+        match res {
             Ok(chatters) => {
                 let _ = self.sender.send((channel.to_string(), chatters));
             }
             Err(err) => {
-                eprintln!("cannot get chatters for: {channel} because: {err}")
+                // we're probably not a moderator there
+                if err.is_auth() {
+                    self.forbidden.insert(channel.to_string());
+                }
+                log::warn!("cannot get chatters for: {channel} because: {err}");
             }
         }
     }
+
+    fn broadcaster_id(
+        ids: &mut HashMap<String, String>,
+        client: &Client,
+        channel: &str,
+    ) -> crate::helix::Result<String> {
+        if let Some(id) = ids.get(channel) {
+            return Ok(id.clone());
+        }
+
+        let user = client
+            .get_users([IdOrLogin::Login(channel)])?
+            .pop()
+            .ok_or_else(|| crate::helix::Error::NotFound(format!("unknown channel: {channel}")))?;
+
+        ids.insert(channel.to_string(), user.id.clone());
+        Ok(user.id)
+    }
 }
@@ -0,0 +1,192 @@
+use poll_promise::Promise;
+
+use crate::{helix, store::EventStore};
+
+/// The scope needed to send whispers through Helix
+pub const WHISPERS_SCOPE: &str = "user:manage:whispers";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct WhisperMessage {
+    pub from: String,
+    pub data: String,
+}
+
+pub enum WhisperLine {
+    Message(i64, WhisperMessage),
+    // these aren't stored
+    Error(i64, String),
+}
+
+/// A conversation with a single user
+pub struct WhisperThread {
+    pub login: String,
+    pub lines: Vec<WhisperLine>,
+    pub unread: usize,
+}
+
+impl WhisperThread {
+    fn push(&mut self, line: WhisperLine) {
+        if self.lines.len() >= Whispers::HISTORY {
+            self.lines.remove(0);
+        }
+        self.lines.push(line)
+    }
+}
+
+/// Whisper threads, the most recent first
+#[derive(Default)]
+pub struct Whispers {
+    threads: Vec<WhisperThread>,
+    pub active: Option<String>,
+    pub reply: String,
+    pub new_login: String,
+    // (to, from, data, sent)
+    sending: Vec<(String, String, String, Promise<helix::Result<()>>)>,
+}
+
+impl Whispers {
+    const KIND: &'static str = "whisper";
+    const HISTORY: usize = 100;
+
+    /// Loads the stored threads
+    pub fn load() -> Self {
+        let mut threads = EventStore::channels(Self::KIND)
+            .into_iter()
+            .map(|login| {
+                let mut lines = EventStore::since(Self::KIND, &login, 0)
+                    .into_iter()
+                    .map(|(at, msg)| WhisperLine::Message(at, msg))
+                    .collect::<Vec<_>>();
+                lines.drain(..lines.len().saturating_sub(Self::HISTORY));
+
+                WhisperThread {
+                    login,
+                    lines,
+                    unread: 0,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        threads.sort_by_key(|thread| {
+            std::cmp::Reverse(thread.lines.last().map(|line| match line {
+                WhisperLine::Message(at, ..) | WhisperLine::Error(at, ..) => *at,
+            }))
+        });
+
+        Self {
+            threads,
+            ..Self::default()
+        }
+    }
+
+    pub fn threads(&self) -> &[WhisperThread] {
+        &self.threads
+    }
+
+    pub fn active_thread(&self) -> Option<&WhisperThread> {
+        let active = self.active.as_deref()?;
+        self.threads.iter().find(|thread| thread.login == active)
+    }
+
+    /// Total unread messages across the threads
+    pub fn unread(&self) -> usize {
+        self.threads.iter().map(|thread| thread.unread).sum()
+    }
+
+    /// Opens the thread with the user, starting one if needed
+    pub fn open(&mut self, login: &str) {
+        let login = login.to_lowercase();
+        self.thread_mut(&login);
+        self.active.replace(login);
+    }
+
+    pub fn mark_read(&mut self, login: &str) {
+        if let Some(thread) = self.threads.iter_mut().find(|thread| thread.login == login) {
+            thread.unread = 0;
+        }
+    }
+
+    /// Someone whispered us
+    pub fn receive(&mut self, from: &str, data: &str) {
+        let thread = self.store(from, from, data);
+        thread.unread += 1;
+    }
+
+    /// We whispered someone
+    pub fn sent(&mut self, to: &str, from: &str, data: &str) {
+        self.store(to, from, data);
+    }
+
+    /// Tracks a whisper being sent through Helix, it's added to the thread once it has been sent
+    pub fn sending(
+        &mut self,
+        to: &str,
+        from: &str,
+        data: &str,
+        promise: Promise<helix::Result<()>>,
+    ) {
+        self.sending.push((
+            to.to_lowercase(),
+            from.to_string(),
+            data.to_string(),
+            promise,
+        ))
+    }
+
+    /// Checks on the whispers being sent, returns whether any finished
+    pub fn poll(&mut self) -> bool {
+        let (done, pending) = std::mem::take(&mut self.sending)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(.., promise)| promise.ready().is_some());
+        self.sending = pending;
+
+        let finished = !done.is_empty();
+        for (to, from, data, promise) in done {
+            match promise.block_and_take() {
+                Ok(()) => self.sent(&to, &from, &data),
+                Err(err) => self.error(&to, format!("cannot send: {err}")),
+            }
+        }
+        finished
+    }
+
+    /// Shows an error in the thread with the user, it isn't stored
+    pub fn error(&mut self, login: &str, err: impl ToString) {
+        let err = err.to_string();
+        log::error!("whisper to {login}: {err}");
+        let at = time::OffsetDateTime::now_utc().unix_timestamp();
+        self.thread_mut(&login.to_lowercase())
+            .push(WhisperLine::Error(at, err));
+    }
+
+    fn store(&mut self, login: &str, from: &str, data: &str) -> &mut WhisperThread {
+        let login = login.to_lowercase();
+        let at = time::OffsetDateTime::now_utc().unix_timestamp();
+        let msg = WhisperMessage {
+            from: from.to_string(),
+            data: data.to_string(),
+        };
+
+        if !EventStore::add(Self::KIND, &login, at, &msg) {
+            log::warn!("cannot store whisper for {login}");
+        }
+
+        let thread = self.thread_mut(&login);
+        thread.push(WhisperLine::Message(at, msg));
+        thread
+    }
+
+    // this moves the thread to the front
+    fn thread_mut(&mut self, login: &str) -> &mut WhisperThread {
+        let thread = match self.threads.iter().position(|thread| thread.login == login) {
+            Some(pos) => self.threads.remove(pos),
+            None => WhisperThread {
+                login: login.to_string(),
+                lines: vec![],
+                unread: 0,
+            },
+        };
+        self.threads.insert(0, thread);
+        &mut self.threads[0]
+    }
+}
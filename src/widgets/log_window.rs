@@ -66,10 +66,7 @@ impl LogWindow {
         queue.push(record);
     }
 
-    pub fn iter(
-        &self,
-        level: log::Level,
-    ) -> impl Iterator<Item = &Record> + ExactSizeIterator + '_ {
+    pub fn iter(&self, level: log::Level) -> impl ExactSizeIterator<Item = &Record> + '_ {
         let queue = match level {
             log::Level::Error => &self.error,
             log::Level::Warn => &self.warn,
@@ -0,0 +1,149 @@
+use egui::{Label, RichText};
+
+use crate::{font_icon, helix::Stream, twitch::RoomSettings, FilterCounts, FilterReason};
+
+pub enum HeaderAction {
+    ToggleModLog,
+    ToggleFiltered,
+}
+
+pub struct ChannelHeader<'a> {
+    stream: Option<&'a Stream>,
+    room: &'a RoomSettings,
+    filter_counts: &'a FilterCounts,
+    show_filtered: bool,
+}
+
+impl<'a> ChannelHeader<'a> {
+    pub const fn new(
+        stream: Option<&'a Stream>,
+        room: &'a RoomSettings,
+        filter_counts: &'a FilterCounts,
+        show_filtered: bool,
+    ) -> Self {
+        Self {
+            stream,
+            room,
+            filter_counts,
+            show_filtered,
+        }
+    }
+
+    pub fn display(self, ui: &mut egui::Ui) -> Option<HeaderAction> {
+        let stream = match self.stream {
+            Some(stream) => stream,
+            None => {
+                return ui
+                    .horizontal(|ui| {
+                        ui.weak("offline");
+                        self.display_room(ui);
+                        self.display_buttons(ui)
+                    })
+                    .inner;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            ui.colored_label(ui.visuals().error_fg_color, font_icon::LIVE);
+            ui.add(Label::new(RichText::new(&stream.title).strong()).wrap(false))
+                .on_hover_text_at_pointer(&stream.title);
+        });
+
+        ui.horizontal(|ui| {
+            if !stream.game_name.is_empty() {
+                ui.small(&stream.game_name);
+                ui.separator();
+            }
+
+            // seconds are just noise here
+            let uptime = stream.uptime() / 60 * 60;
+            match uptime {
+                0 => ui.small("just started"),
+                _ => ui.small(format!("up for {}", crate::format_seconds(uptime))),
+            };
+            ui.separator();
+
+            ui.small(format!("{} viewers", stream.viewer_count));
+            self.display_room(ui);
+            self.display_buttons(ui)
+        })
+        .inner
+    }
+
+    fn display_buttons(&self, ui: &mut egui::Ui) -> Option<HeaderAction> {
+        ui.separator();
+        let mut action = None;
+        if ui
+            .small_button(font_icon::MOD_LOG)
+            .on_hover_text_at_pointer("mod log")
+            .clicked()
+        {
+            action.replace(HeaderAction::ToggleModLog);
+        }
+
+        let total = self.filter_counts.total();
+        if total == 0 {
+            return action;
+        }
+
+        let resp = ui
+            .selectable_label(
+                self.show_filtered,
+                RichText::new(format!("{} {total}", font_icon::FILTERED)).small(),
+            )
+            .on_hover_ui_at_pointer(|ui| {
+                ui.label(match self.show_filtered {
+                    true => "hide filtered lines",
+                    false => "show filtered lines",
+                });
+                for reason in FilterReason::ALL {
+                    let count = self.filter_counts.get(reason);
+                    if count > 0 {
+                        ui.small(format!("{count} from {}", reason.display()));
+                    }
+                }
+            });
+        if resp.clicked() {
+            action.replace(HeaderAction::ToggleFiltered);
+        }
+        action
+    }
+
+    fn display_room(&self, ui: &mut egui::Ui) {
+        let room = self.room;
+
+        let mut modes = vec![];
+        if let Some(slow) = room.slow {
+            modes.push((
+                font_icon::SLOW_MODE,
+                format!("slow mode: {}", crate::format_seconds(slow)),
+            ));
+        }
+        match room.followers_only {
+            Some(0) => modes.push((font_icon::FOLLOWERS_ONLY, "followers only".to_string())),
+            Some(minutes) => modes.push((
+                font_icon::FOLLOWERS_ONLY,
+                format!("followers only: {}", crate::format_seconds(minutes * 60)),
+            )),
+            None => {}
+        }
+        if room.subs_only {
+            modes.push((font_icon::SUBS_ONLY, "subscribers only".to_string()));
+        }
+        if room.emote_only {
+            modes.push((font_icon::EMOTE_ONLY, "emote only".to_string()));
+        }
+        if room.r9k {
+            modes.push((font_icon::R9K, "unique chat (r9k)".to_string()));
+        }
+
+        if modes.is_empty() {
+            return;
+        }
+
+        ui.separator();
+        for (icon, text) in modes {
+            ui.small(icon).on_hover_text_at_pointer(text);
+        }
+    }
+}
@@ -1,47 +1,185 @@
 use std::collections::HashMap;
 
-use egui::{Label, TextStyle};
+use egui::{vec2, Color32, Frame, Label, RichText, Sense, Stroke, TextStyle, Vec2};
 
 use time::OffsetDateTime;
 
 use crate::{
-    twitch::{self, EmoteSpan},
-    ImageCache,
+    fetch::ImageKind,
+    helix,
+    store::Image,
+    twitch::{self, EmoteSpan, UserNoticeKind},
+    ChannelAssets, Endpoints, FetchQueue, Highlight, ImageCache,
 };
 
 use super::Timestamp;
 
+#[derive(Clone)]
 pub struct ChatLine {
     pub ts: Timestamp,
     pub id: uuid::Uuid,
     pub spans: Vec<EmoteSpan>,
     pub msg: twitch::Message,
+    /// A moderator deleted this, or timed out the sender
+    pub deleted: bool,
+    pub highlight: Option<Highlight>,
+    /// Our local echo, its id isn't one Twitch knows about
+    pub local: bool,
+}
+
+impl ChatLine {
+    /// The message, either a privmsg or the one attached to a user notice
+    pub fn privmsg(&self) -> Option<twitch::Privmsg<'_>> {
+        self.msg
+            .as_privmsg()
+            .or_else(|| self.msg.as_user_notice()?.message())
+    }
+}
+
+/// A sub, raid, announcement, etc
+pub struct NoticeLine {
+    pub ts: Timestamp,
+    pub kind: UserNoticeKind,
+    pub system_msg: String,
+    pub message: Option<ChatLine>,
+}
+
+impl NoticeLine {
+    fn accent(&self) -> Color32 {
+        match &self.kind {
+            UserNoticeKind::Announcement { color } => match &**color {
+                "BLUE" => Color32::from_rgb(0x00, 0xD6, 0xE0),
+                "GREEN" => Color32::from_rgb(0x00, 0xDB, 0x84),
+                "ORANGE" => Color32::from_rgb(0xFF, 0xB3, 0x1A),
+                "PURPLE" => Color32::from_rgb(0x9C, 0x7D, 0xFF),
+                _ => crate::TWITCH_COLOR,
+            },
+            UserNoticeKind::Raid { .. } => Color32::from_rgb(0xFF, 0xB3, 0x1A),
+            UserNoticeKind::BitsBadgeTier { .. } => Color32::GOLD,
+            _ => crate::TWITCH_COLOR,
+        }
+    }
+}
+
+pub struct NoticeLineView<'a> {
+    line: &'a NoticeLine,
+    show_timestamp: bool,
+}
+
+impl<'a> NoticeLineView<'a> {
+    pub const fn new(line: &'a NoticeLine, show_timestamp: bool) -> Self {
+        Self {
+            line,
+            show_timestamp,
+        }
+    }
+
+    /// `message` draws the attached message, and returns whether the sender was clicked
+    pub fn display(
+        self,
+        ui: &mut egui::Ui,
+        message: impl FnOnce(&mut egui::Ui, &ChatLine) -> bool,
+    ) -> bool {
+        let accent = self.line.accent();
+        Frame::none()
+            .fill(accent.linear_multiply(0.1))
+            .stroke(Stroke::new(1.0, accent))
+            .inner_margin(4.0)
+            .show(ui, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    if self.show_timestamp {
+                        ui.small(self.line.ts.as_str());
+                    }
+                    ui.add(Label::new(RichText::new(&self.line.system_msg).strong()));
+                });
+
+                self.line
+                    .message
+                    .as_ref()
+                    .map(|line| message(ui, line))
+                    .unwrap_or_default()
+            })
+            .inner
+    }
+}
+
+/// A line from us, rather than from chat
+pub struct SystemLine {
+    pub ts: Timestamp,
+    pub text: String,
+}
+
+pub struct SystemLineView<'a> {
+    line: &'a SystemLine,
+    show_timestamp: bool,
+}
+
+impl<'a> SystemLineView<'a> {
+    pub const fn new(line: &'a SystemLine, show_timestamp: bool) -> Self {
+        Self {
+            line,
+            show_timestamp,
+        }
+    }
+
+    pub fn display(self, ui: &mut egui::Ui) {
+        ui.horizontal_wrapped(|ui| {
+            if self.show_timestamp {
+                ui.small(self.line.ts.as_str());
+            }
+            ui.add(Label::new(RichText::new(&self.line.text).italics().weak()));
+        });
+    }
 }
 
 pub struct ChatLineView<'a> {
     line: &'a ChatLine,
-    cache: &'a ImageCache,
+    cache: &'a mut ImageCache,
+    fetch: &'a mut FetchQueue<Image>,
+    assets: Option<&'a ChannelAssets>,
+    global_badges: &'a [helix::Badges],
     emote_map: &'a HashMap<String, String>,
+    endpoints: &'a Endpoints,
     show_timestamp: bool,
 }
 
 impl<'a> ChatLineView<'a> {
-    pub const fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
         line: &'a ChatLine,
-        cache: &'a ImageCache,
+        cache: &'a mut ImageCache,
+        fetch: &'a mut FetchQueue<Image>,
+        assets: Option<&'a ChannelAssets>,
+        global_badges: &'a [helix::Badges],
         emote_map: &'a HashMap<String, String>,
+        endpoints: &'a Endpoints,
         show_timestamp: bool,
     ) -> Self {
         Self {
             line,
             cache,
+            fetch,
+            assets,
+            global_badges,
             emote_map,
+            endpoints,
             show_timestamp,
         }
     }
 
-    pub fn display(&self, ui: &mut egui::Ui) {
-        let pm = self.line.msg.as_privmsg().expect("this must be a privmsg");
+    const BADGE_SIZE: Vec2 = vec2(8.0, 8.0);
+    const EMOTE_SIZE: Vec2 = vec2(16.0, 16.0);
+
+    /// Returns whether the sender's name was clicked
+    pub fn display(mut self, ui: &mut egui::Ui) -> bool {
+        Self::frame(self.line.highlight)
+            .show(ui, |ui| self.display_line(ui))
+            .inner
+    }
+
+    fn display_line(&mut self, ui: &mut egui::Ui) -> bool {
+        let line = self.line;
+        let pm = line.privmsg().expect("this must be a privmsg");
 
         ui.horizontal_wrapped(|ui| {
             if self.show_timestamp {
@@ -61,33 +199,183 @@ impl<'a> ChatLineView<'a> {
                     .glyph_width(&TextStyle::Body.resolve(ui.style()), ' ');
                 ui.spacing_mut().item_spacing.x = width;
 
-                // if let Some((badge, version)) = pm.badges().next() {
-                //     if let Some(img) = self.cache.get(badge) {
-                //         img.show_size(ui, vec2(8.0, 8.0));
-                //         // .on_hover_text_at_pointer(self.emote_map.get(badge).unwrap());
-                //     }
-                // }
+                for (set_id, id) in pm.badges() {
+                    // channel badges override the global ones (e.g. subscriber badges)
+                    let version = match self
+                        .assets
+                        .and_then(|assets| assets.find_badge(set_id, id))
+                        .or_else(|| helix::Badges::find(self.global_badges, set_id, id))
+                    {
+                        Some(version) => version,
+                        None => continue,
+                    };
 
-                ui.colored_label(pm.color(), pm.sender);
+                    if let Some(resp) = Self::show_image(
+                        self.cache,
+                        self.fetch,
+                        &version.image_url_1x,
+                        ImageKind::Badge,
+                        Self::BADGE_SIZE,
+                        ui,
+                    ) {
+                        resp.on_hover_text_at_pointer(set_id);
+                    }
+                }
+
+                let clicked = ui
+                    .add(
+                        Label::new(RichText::new(pm.sender).color(pm.color()))
+                            .sense(Sense::click()),
+                    )
+                    .on_hover_cursor(egui::CursorIcon::PointingHand)
+                    .clicked();
 
-                for spans in &self.line.spans {
+                let show_id = egui::Id::new(("show_deleted", line.id));
+                let shown = ui.data().get_temp::<bool>(show_id).unwrap_or_default();
+                if line.deleted && !shown {
+                    if ui
+                        .add(
+                            Label::new(RichText::new("<message deleted>").italics().weak())
+                                .sense(Sense::click()),
+                        )
+                        .on_hover_text_at_pointer("show anyway")
+                        .on_hover_cursor(egui::CursorIcon::PointingHand)
+                        .clicked()
+                    {
+                        ui.data().insert_temp(show_id, true);
+                    }
+                    return clicked;
+                }
+
+                for spans in &line.spans {
                     match spans {
-                    EmoteSpan::Emote(s) =>
-                    // match self.cache.get(s) {
-                        // Some(img) => {
-                        //     img.show_size(ui, vec2(16.0, 16.0))
-                        //         .on_hover_text_at_pointer(self.emote_map.get(s).unwrap());
-                        // }
-                        // None => {
-                           { ui.add(Label::new(s));}
-                        // }
-                    // },
-                    EmoteSpan::Text(s) => {
-                        ui.add(Label::new(s));
+                        EmoteSpan::Emote(s) => {
+                            let name = self.emote_map.get(s).map(|s| &**s).unwrap_or(s);
+                            match Self::show_image(
+                                self.cache,
+                                self.fetch,
+                                &self.endpoints.emote_url(s),
+                                ImageKind::Emote,
+                                Self::EMOTE_SIZE,
+                                ui,
+                            ) {
+                                Some(resp) => {
+                                    resp.on_hover_text_at_pointer(name);
+                                }
+                                None => {
+                                    ui.add(Label::new(name));
+                                }
+                            }
+                        }
+                        EmoteSpan::Text(s) if line.deleted => {
+                            ui.add(Label::new(RichText::new(s).strikethrough().weak()));
+                        }
+                        // `/me` lines are drawn in the sender's color
+                        EmoteSpan::Text(s) if pm.action => {
+                            ui.add(Label::new(RichText::new(s).color(pm.color())));
+                        }
+                        EmoteSpan::Text(s) => {
+                            ui.add(Label::new(s));
+                        }
+                        EmoteSpan::Cheer { prefix, bits } => self.show_cheer(prefix, *bits, ui),
                     }
                 }
+
+                if line.deleted
+                    && ui
+                        .small_button("hide")
+                        .on_hover_text_at_pointer("this message was deleted")
+                        .clicked()
+                {
+                    ui.data().insert_temp(show_id, false);
                 }
-            });
-        });
+
+                if let Some(bits) = pm.bits() {
+                    ui.add(Label::new(
+                        RichText::new(format!("{bits} bits"))
+                            .small()
+                            .strong()
+                            .color(crate::TWITCH_COLOR),
+                    ));
+                }
+
+                clicked
+            })
+            .inner
+        })
+        .inner
+    }
+
+    fn frame(highlight: Option<Highlight>) -> Frame {
+        match highlight {
+            Some(highlight) => Frame::none().fill(highlight.color.linear_multiply(0.15)),
+            None => Frame::none(),
+        }
+    }
+
+    fn show_cheer(&mut self, prefix: &str, bits: u64, ui: &mut egui::Ui) {
+        let text = format!("{prefix}{bits}");
+        let tier = match self
+            .assets
+            .and_then(|assets| assets.find_cheermote(prefix))
+            .and_then(|cheermote| cheermote.tier(bits))
+        {
+            Some(tier) => tier,
+            None => {
+                ui.add(Label::new(text));
+                return;
+            }
+        };
+
+        let color = tier
+            .color
+            .parse::<twitch::Color>()
+            .map(Color32::from)
+            .unwrap_or(crate::TWITCH_COLOR);
+
+        let images = match ui.visuals().dark_mode {
+            true => &tier.images.dark,
+            false => &tier.images.light,
+        };
+
+        if let Some(url) = images.still.get("2") {
+            if let Some(resp) = Self::show_image(
+                self.cache,
+                self.fetch,
+                url,
+                ImageKind::Emote,
+                Self::EMOTE_SIZE,
+                ui,
+            ) {
+                resp.on_hover_text_at_pointer(&text);
+            }
+        }
+
+        ui.add(Label::new(
+            RichText::new(bits.to_string()).strong().color(color),
+        ));
+    }
+
+    pub(super) fn show_image(
+        cache: &mut ImageCache,
+        fetch: &mut FetchQueue<Image>,
+        url: &str,
+        kind: ImageKind,
+        size: Vec2,
+        ui: &mut egui::Ui,
+    ) -> Option<egui::Response> {
+        let id = cache.id_for_url(url);
+        match cache.get_id(id) {
+            Some(img) => Some(img.show_max_size(ui, size)),
+            None => {
+                fetch.fetch(Image {
+                    id,
+                    url: url.to_string(),
+                    kind,
+                    meta: (),
+                });
+                None
+            }
+        }
     }
 }
@@ -1,8 +1,25 @@
-use egui::{CentralPanel, Color32, CursorIcon, Id, PointerButton, Rounding, Sense};
+use egui::{
+    vec2, Align, CentralPanel, Color32, CursorIcon, Direction, Frame, Id, Layout, PointerButton,
+    Rect, Response, RichText, Rounding, ScrollArea, Sense, SidePanel, Stroke, TopBottomPanel, Vec2,
+};
 
-use crate::state::AppState;
+use poll_promise::Promise;
 
-use super::{Position, TabBar, TabView};
+use crate::{
+    fetch::ImageKind,
+    helix::{self, IdOrLogin, Kind},
+    moderation::ModAction,
+    state::AppState,
+    store::Image,
+    twitch,
+    whispers::WHISPERS_SCOPE,
+};
+
+use super::{
+    state::Line, ChannelHeader, ChatLineView, ChatViewState, EditBox, HeaderAction, ModLogView,
+    ModerationMenu, NoticeLineView, Position, SystemLineView, TabBar, TabView, UserCard,
+    UserCardAction, UserCardView, UserList, WhisperView,
+};
 
 pub struct ChatView<'a> {
     state: &'a mut AppState,
@@ -14,7 +31,7 @@ impl<'a> ChatView<'a> {
         Self { state, writer }
     }
 
-    pub fn display(self, ctx: &egui::Context) {
+    pub fn display(mut self, ctx: &egui::Context) {
         CentralPanel::default().show(ctx, |ui| {
             let cvs = &mut self.state.state.chat_view_state;
 
@@ -34,126 +51,596 @@ impl<'a> ChatView<'a> {
                 },
             );
 
-            let resp = ui.interact(rect, id, Sense::click_and_drag());
+            // the tab bar is drawn over this panel, so the chat gets whatever is left
+            let remaining = cvs.tab_bar_position.remaining(ui.max_rect(), rect);
+            let clicked = ui
+                .allocate_ui_at_rect(remaining, |ui| self.display_active(ui))
+                .inner;
+
+            if let Some((login, channel)) = clicked {
+                self.state.state.chat_view_state.user_card =
+                    Some(UserCard::open(self.state.runtime.helix(), &login, &channel));
+            }
+            self.display_user_card(ctx);
+
+            self.handle_tab_bar_drag(ctx, ui, id, rect);
+        });
+    }
+
+    /// Returns the user (and channel) that was clicked on
+    fn display_active(&mut self, ui: &mut egui::Ui) -> Option<(String, String)> {
+        let focused = ui.input().raw.has_focus;
+        self.state.state.chat_view_state.update_shown(focused);
+
+        if self.state.state.chat_view_state.show_whispers {
+            self.display_whispers(ui);
+            return None;
+        }
+        if self.state.state.chat_view_state.show_mentions {
+            self.display_mentions(ui);
+            return None;
+        }
+
+        let state = &mut self.state.state;
+        let runtime = &mut self.state.runtime;
+
+        let channel_state = state.chat_view_state.active_mut()?;
+        channel_state.mark_read();
+        let mut clicked = None;
+
+        let channel = state
+            .channels
+            .iter()
+            .find(|c| ChatViewState::is_same_channel(&c.login, channel_state.name()));
+
+        let show_user_list = channel.map(|c| c.show_user_list).unwrap_or(true);
+        let show_timestamp = channel.map(|c| c.show_timestamps).unwrap_or(true);
+        let assets = channel.and_then(|c| state.channel_assets.get(&c.id));
 
-            if resp.dragged_by(PointerButton::Primary) && ui.input().modifiers.shift_only() {
-                ui.output().cursor_icon = CursorIcon::Grab;
+        let global_badges = runtime
+            .global_badges
+            .ready()
+            .map(|badges| &**badges)
+            .unwrap_or_default();
 
-                let mouse_pos = ui.input().pointer.hover_pos().unwrap();
+        let kind = channel_state.our_kind(self.state.identity.as_ref());
+        let is_moderator = matches!(kind, Some(Kind::Broadcaster | Kind::Moderator));
+        let mut moderate = None;
 
-                let landing = Position::rects(cvs.image_size, self.state.state.window_size);
-                let distance = landing.map(|(_, rect)| rect.signed_distance_to_pos(mouse_pos));
+        // only the channel emotes we can use, when we know who we are here
+        let emotes = assets
+            .iter()
+            .flat_map(|assets| &assets.emotes)
+            .filter(|emote| {
+                channel_state
+                    .identity
+                    .as_ref()
+                    .is_none_or(|id| id.can_use(emote))
+            })
+            .map(|emote| &*emote.name)
+            .chain(state.emote_map.values().map(|name| &**name))
+            .collect::<Vec<_>>();
 
-                if let Some((pos, rect)) = distance
-                    .into_iter()
-                    .enumerate()
-                    .find_map(|(i, c)| (c < cvs.image_size * 0.5).then_some(i))
-                    .map(|index| landing[index])
+        let mut sent = None;
+
+        TopBottomPanel::bottom("input")
+            .resizable(false)
+            .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+            .show_inside(ui, |ui| {
+                ui.with_layout(
+                    Layout::centered_and_justified(Direction::LeftToRight),
+                    |ui| {
+                        sent = EditBox::new(
+                            &mut channel_state.buffer.buffer,
+                            &self.writer,
+                            &channel_state.room,
+                            &mut runtime.sent,
+                            &mut channel_state.last_sent,
+                            &emotes,
+                            kind,
+                        )
+                        .display(ui);
+                    },
+                );
+            });
+
+        if let (Some(data), Some(identity), Some(me)) =
+            (sent, &channel_state.identity, self.state.identity.as_ref())
+        {
+            let msg = twitch::Message::local_echo(
+                &channel_state.channel,
+                &me.user_name.to_lowercase(),
+                identity,
+                &data,
+            );
+            if let Some(msg) = msg {
+                if let Some((id, spans)) = msg.as_privmsg().map(|pm| pm.make_spans(|_| false)) {
+                    channel_state.push_local_echo(id, spans, msg);
+                }
+            }
+        }
+
+        TopBottomPanel::top("channel_header")
+            .resizable(false)
+            .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+            .show_inside(ui, |ui| {
+                match ChannelHeader::new(
+                    channel_state.stream(),
+                    &channel_state.room,
+                    &channel_state.filter_counts,
+                    channel_state.show_filtered,
+                )
+                .display(ui)
                 {
-                    ui.data().insert_temp(Id::new("tab_bar_drag_pos"), pos);
+                    Some(HeaderAction::ToggleModLog) => {
+                        channel_state.show_mod_log = !channel_state.show_mod_log
+                    }
+                    Some(HeaderAction::ToggleFiltered) => {
+                        channel_state.show_filtered = !channel_state.show_filtered
+                    }
+                    None => {}
+                }
+            });
+
+        if channel_state.show_mod_log {
+            SidePanel::right("mod_log")
+                .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+                .show_inside(ui, |ui| {
+                    ModLogView::new(&channel_state.mod_log, &mut channel_state.mod_log_search)
+                        .display(ui);
+                });
+        }
+
+        if show_user_list {
+            SidePanel::right("user_list")
+                .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+                .show_inside(ui, |ui| {
+                    if let Some(login) =
+                        UserList::new(&channel_state.chatters, &state.images).display(ui)
+                    {
+                        clicked.replace(login);
+                    }
+                });
+        }
 
-                    // don't show the ghost if we're already showing the real thing
-                    if pos == cvs.tab_bar_position {
-                        return;
+        let scroll_to = channel_state.scroll_to.take();
+        let scroll_to_last_read = std::mem::take(&mut channel_state.scroll_to_last_read);
+        let last_read = channel_state.last_read;
+        let mut marker_drawn = false;
+
+        // egui pins the offset to the bottom after scrolling to a line or the marker, so stop
+        // sticking for the jump. it remembers we aren't at the bottom, so it stays off until
+        // we scroll back down
+        let jumping = scroll_to.is_some() || scroll_to_last_read;
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(!jumping)
+            .show(ui, |ui| {
+                for (line, filtered) in channel_state.visible_lines() {
+                    if !marker_drawn && last_read.is_some_and(|at| line.ts().date_time > at) {
+                        marker_drawn = true;
+                        let resp = Self::display_last_read_marker(ui);
+                        if scroll_to_last_read {
+                            resp.scroll_to_me(Some(Align::Center));
+                        }
+                    }
+
+                    let frame = match filtered {
+                        true => Frame::none().fill(ui.visuals().faint_bg_color),
+                        false => Frame::none(),
+                    };
+                    let resp = frame.show(ui, |ui| match line {
+                        Line::Chat(line) => {
+                            let resp = ui.scope(|ui| {
+                                ChatLineView::new(
+                                    line,
+                                    &mut state.images,
+                                    &mut runtime.fetch,
+                                    assets,
+                                    global_badges,
+                                    &state.emote_map,
+                                    &state.endpoints,
+                                    show_timestamp,
+                                )
+                                .display(ui)
+                            });
+
+                            if resp.inner {
+                                let pm = line.msg.as_privmsg();
+                                clicked = pm.map(|pm| pm.sender.to_string());
+                            }
+
+                            ui.interact(resp.response.rect, ui.id().with(line.id), Sense::click())
+                                .context_menu(|ui| {
+                                    if let Some(action) =
+                                        ModerationMenu::new(line, is_moderator).display(ui)
+                                    {
+                                        moderate.replace(action);
+                                    }
+                                });
+                        }
+                        Line::System(line) => {
+                            SystemLineView::new(line, show_timestamp).display(ui);
+                        }
+                        Line::Notice(notice) => {
+                            let sender = NoticeLineView::new(notice, show_timestamp).display(
+                                ui,
+                                |ui, line| {
+                                    ChatLineView::new(
+                                        line,
+                                        &mut state.images,
+                                        &mut runtime.fetch,
+                                        assets,
+                                        global_badges,
+                                        &state.emote_map,
+                                        &state.endpoints,
+                                        false,
+                                    )
+                                    .display(ui)
+                                },
+                            );
+
+                            if sender {
+                                let pm = notice.message.as_ref().and_then(|line| line.privmsg());
+                                clicked = pm.map(|pm| pm.sender.to_string());
+                            }
+                        }
+                    });
+
+                    if scroll_to.is_some() && line.message().map(|line| line.id) == scroll_to {
+                        resp.response.scroll_to_me(Some(Align::Center));
+                    }
+                }
+            });
+
+        let channel = channel_state.name().to_string();
+        if let Some(action) = moderate {
+            self.moderate(ui.ctx(), &channel, action);
+        }
+
+        clicked.map(|login| (login, channel))
+    }
+
+    /// Moderates through Helix if the token can, otherwise with a chat command
+    fn moderate(&mut self, ctx: &egui::Context, channel: &str, action: ModAction) {
+        let token = &self.state.state.config.twitch_oauth_token;
+        let tokens = &self.state.runtime.tokens;
+        let info = (!tokens.is_stale(token))
+            .then(|| tokens.info())
+            .flatten()
+            .filter(|info| info.has_scope(action.scope()));
+
+        let broadcaster_id = self
+            .state
+            .state
+            .channels
+            .iter()
+            .find(|c| ChatViewState::is_same_channel(&c.login, channel))
+            .map(|c| c.id.to_string());
+
+        match (self.state.runtime.helix(), info, broadcaster_id) {
+            (Some(helix), Some(info), Some(broadcaster_id)) => {
+                let helix = helix.for_user(&info.client_id, token);
+                let moderator_id = info.user_id.clone();
+                let ctx = ctx.clone();
+
+                let promise = Promise::spawn_thread("moderation", {
+                    let action = action.clone();
+                    move || {
+                        let res = action.run(&helix, &broadcaster_id, &moderator_id);
+                        ctx.request_repaint();
+                        res
                     }
+                });
+                self.state.runtime.moderation.push(channel, action, promise);
+            }
+            // twitch replies with a notice
+            _ => self.state.send_message(channel, &action.command()),
+        }
+    }
+
+    fn display_last_read_marker(ui: &mut egui::Ui) -> Response {
+        ui.horizontal(|ui| {
+            let color = ui.visuals().error_fg_color;
+            ui.small(RichText::new("new messages").color(color));
+            let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), 1.0), Sense::hover());
+            ui.painter().hline(
+                rect.left()..=rect.right(),
+                rect.center().y,
+                Stroke::new(1.0, color),
+            );
+        })
+        .response
+    }
+
+    /// Highlighted lines from every channel, clicking one jumps to it
+    fn display_mentions(&mut self, ui: &mut egui::Ui) {
+        const AVATAR_SIZE: Vec2 = vec2(16.0, 16.0);
+
+        let state = &mut self.state.state;
+        let runtime = &mut self.state.runtime;
 
-                    ctx.move_to_top(ui.layer_id());
-
-                    let (id, rect) = TabBar::new(pos, cvs.image_size).display(
-                        ctx,
-                        "temp_tab_bar",
-                        |ui: &mut egui::Ui| {
-                            TabView::new(
-                                &mut self.state.state.images,
-                                cvs,
-                                &mut self.state.runtime.fetch,
-                                &mut self.state.state.channels,
-                                &self.state.dark_image_mask,
-                                self.state.state.window_size,
+        let global_badges = runtime
+            .global_badges
+            .ready()
+            .map(|badges| &**badges)
+            .unwrap_or_default();
+
+        if state.chat_view_state.mentions.is_empty() {
+            ui.centered_and_justified(|ui| ui.weak("nothing has been highlighted yet"));
+            return;
+        }
+
+        let mut jump = None;
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for mention in state.chat_view_state.mentions.iter() {
+                    let channel = state
+                        .channels
+                        .iter()
+                        .find(|c| ChatViewState::is_same_channel(&c.login, &mention.channel));
+                    let assets = channel.and_then(|c| state.channel_assets.get(&c.id));
+
+                    let resp = ui.horizontal_top(|ui| {
+                        match channel.and_then(|c| state.images.get_id(c.image_id)) {
+                            Some(img) => {
+                                img.show_max_size(ui, AVATAR_SIZE)
+                                    .on_hover_text_at_pointer(&mention.channel);
+                            }
+                            None => {
+                                if let Some(channel) = channel {
+                                    runtime.fetch.fetch(Image {
+                                        id: channel.image_id,
+                                        kind: ImageKind::Display,
+                                        url: channel.profile_image_url.clone(),
+                                        meta: (),
+                                    });
+                                }
+                                ui.small(&mention.channel);
+                            }
+                        }
+
+                        ui.vertical(|ui| {
+                            ChatLineView::new(
+                                &mention.line,
+                                &mut state.images,
+                                &mut runtime.fetch,
+                                assets,
+                                global_badges,
+                                &state.emote_map,
+                                &state.endpoints,
+                                true,
                             )
-                            .display(ui);
-                        },
-                    );
-
-                    // this is the ghost
-                    ui.painter().rect(
-                        rect,
-                        Rounding::none(),
-                        Color32::from_black_alpha(0x99),
-                        ui.style().visuals.selection.stroke,
-                    );
+                            .display(ui)
+                        })
+                        .inner
+                    });
+
+                    let id = ui.id().with(("mention", mention.line.id));
+                    let line = ui
+                        .interact(resp.response.rect, id, Sense::click())
+                        .on_hover_cursor(CursorIcon::PointingHand)
+                        .on_hover_text_at_pointer(format!("jump to {}", mention.channel));
+                    if resp.inner || line.clicked() {
+                        jump.replace((mention.channel.clone(), mention.line.id));
+                    }
                 }
+            });
+
+        if let Some((channel, id)) = jump {
+            state.chat_view_state.jump_to(&channel, id);
+        }
+    }
+
+    fn display_whispers(&mut self, ui: &mut egui::Ui) {
+        let whispers = &mut self.state.state.chat_view_state.whispers;
+        if let Some((to, data)) = WhisperView::new(whispers, true).display(ui) {
+            self.send_whisper(ui.ctx(), &to, &data);
+        }
+    }
+
+    /// Whispers through Helix if the token can, otherwise with `/w`
+    fn send_whisper(&mut self, ctx: &egui::Context, to: &str, data: &str) {
+        let from = match &self.state.identity {
+            Some(identity) => identity.user_name.clone(),
+            None => return,
+        };
+
+        let token = &self.state.state.config.twitch_oauth_token;
+        let tokens = &self.state.runtime.tokens;
+        let info = (!tokens.is_stale(token))
+            .then(|| tokens.info())
+            .flatten()
+            .filter(|info| info.has_scope(WHISPERS_SCOPE));
+
+        match (self.state.runtime.helix(), info) {
+            (Some(helix), Some(info)) => {
+                let helix = helix.for_user(&info.client_id, token);
+                let from_id = info.user_id.clone();
+                let (login, message) = (to.to_string(), data.to_string());
+                let ctx = ctx.clone();
+
+                let promise = Promise::spawn_thread("whisper", move || {
+                    let res = helix
+                        .get_users([IdOrLogin::Login(&login)])
+                        .and_then(|mut users| {
+                            users.pop().ok_or_else(|| {
+                                helix::Error::NotFound(format!("unknown user: {login}"))
+                            })
+                        })
+                        .and_then(|user| helix.send_whisper(&from_id, &user.id, &message));
+                    ctx.request_repaint();
+                    res
+                });
+
+                self.state
+                    .state
+                    .chat_view_state
+                    .whispers
+                    .sending(to, &from, data, promise);
             }
+            // without a token that can whisper, irc is all we have
+            _ => {
+                self.state.send_message("#jtv", &format!("/w {to} {data}"));
+                self.state
+                    .state
+                    .chat_view_state
+                    .whispers
+                    .sent(to, &from, data);
+            }
+        }
+    }
+
+    fn display_user_card(&mut self, ctx: &egui::Context) {
+        let state = &mut self.state.state;
+        let runtime = &mut self.state.runtime;
 
-            if resp.drag_released() {
-                let mut data = ui.data();
-                cvs.tab_bar_position = data
-                    .get_temp(Id::new("tab_bar_drag_pos"))
-                    .unwrap_or(cvs.tab_bar_position);
-                data.remove::<Position>(Id::new("tab_bar_drag_pos"));
+        let card = match &state.chat_view_state.user_card {
+            Some(card) => card,
+            None => return,
+        };
+
+        let channel_state = match state
+            .chat_view_state
+            .channels
+            .iter()
+            .find(|c| ChatViewState::is_same_channel(c.name(), &card.channel))
+        {
+            Some(channel_state) => channel_state,
+            None => {
+                state.chat_view_state.user_card.take();
+                return;
             }
-        });
+        };
+
+        let assets = state
+            .channels
+            .iter()
+            .find(|c| ChatViewState::is_same_channel(&c.login, &card.channel))
+            .and_then(|c| state.channel_assets.get(&c.id));
+
+        let global_badges = runtime
+            .global_badges
+            .ready()
+            .map(|badges| &**badges)
+            .unwrap_or_default();
+
+        let is_moderator = channel_state
+            .our_kind(self.state.identity.as_ref())
+            .filter(|kind| matches!(kind, Kind::Broadcaster | Kind::Moderator))
+            .is_some();
+
+        let action = UserCardView::new(
+            card,
+            &channel_state.lines,
+            &mut state.images,
+            &mut runtime.fetch,
+            assets,
+            global_badges,
+            is_moderator,
+        )
+        .display(ctx);
+
+        let action = match action {
+            Some(action) => action,
+            None => return,
+        };
+
+        let channel = card.channel.clone();
+        match action {
+            UserCardAction::Mention(login) => {
+                if let Some(channel_state) = state.chat_view_state.get_mut_by_name(&channel) {
+                    let buffer = &mut channel_state.buffer.buffer;
+                    if !buffer.is_empty() && !buffer.ends_with(' ') {
+                        buffer.push(' ');
+                    }
+                    buffer.push_str(&format!("@{login} "));
+                }
+            }
+            UserCardAction::Whisper(login) => {
+                state.chat_view_state.whispers.open(&login);
+                state.chat_view_state.show_whispers = true;
+                state.chat_view_state.user_card.take();
+            }
+            UserCardAction::Ignore(login) => {
+                state.ignored_users.insert(login);
+                state.chat_view_state.user_card.take();
+            }
+            UserCardAction::Timeout(login, secs) => {
+                self.moderate(ctx, &channel, ModAction::Timeout { login, secs });
+            }
+            UserCardAction::Ban(login) => {
+                self.moderate(ctx, &channel, ModAction::Ban { login });
+            }
+            UserCardAction::Close => {
+                state.chat_view_state.user_card.take();
+            }
+        }
+    }
+
+    fn handle_tab_bar_drag(&mut self, ctx: &egui::Context, ui: &mut egui::Ui, id: Id, rect: Rect) {
+        let cvs = &mut self.state.state.chat_view_state;
+
+        let resp = ui.interact(rect, id, Sense::click_and_drag());
+
+        if resp.dragged_by(PointerButton::Primary) && ui.input().modifiers.shift_only() {
+            ui.output().cursor_icon = CursorIcon::Grab;
+
+            let mouse_pos = ui.input().pointer.hover_pos().unwrap();
+
+            let landing = Position::rects(cvs.image_size, self.state.state.window_size);
+            let distance = landing.map(|(_, rect)| rect.signed_distance_to_pos(mouse_pos));
+
+            if let Some((pos, rect)) = distance
+                .into_iter()
+                .enumerate()
+                .find_map(|(i, c)| (c < cvs.image_size * 0.5).then_some(i))
+                .map(|index| landing[index])
+            {
+                ui.data().insert_temp(Id::new("tab_bar_drag_pos"), pos);
+
+                // don't show the ghost if we're already showing the real thing
+                if pos == cvs.tab_bar_position {
+                    return;
+                }
+
+                ctx.move_to_top(ui.layer_id());
+
+                let (id, rect) = TabBar::new(pos, cvs.image_size).display(
+                    ctx,
+                    "temp_tab_bar",
+                    |ui: &mut egui::Ui| {
+                        TabView::new(
+                            &mut self.state.state.images,
+                            cvs,
+                            &mut self.state.runtime.fetch,
+                            &mut self.state.state.channels,
+                            &self.state.dark_image_mask,
+                            self.state.state.window_size,
+                        )
+                        .display(ui);
+                    },
+                );
+
+                // this is the ghost
+                ui.painter().rect(
+                    rect,
+                    Rounding::none(),
+                    Color32::from_black_alpha(0x99),
+                    ui.style().visuals.selection.stroke,
+                );
+            }
+        }
 
-        // let state = match cvs.active_mut() {
-        //     Some(state) => state,
-        //     None => return,
-        // };
-
-        // let buf = &mut state.buffer;
-
-        // TopBottomPanel::bottom("input")
-        //     .resizable(false)
-        //     .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
-        //     .show_inside(ui, |ui| {
-        //         ui.with_layout(
-        //             Layout::centered_and_justified(Direction::LeftToRight),
-        //             |ui| {
-        //                 EditBox::new(&mut buf.buffer, &self.writer).display(ui);
-        //             },
-        //         );
-        //     });
-
-        // if let Some(channel) = self
-        //     .state
-        //     .state
-        //     .channels
-        //     .iter_mut()
-        //     .find(|c| ChatViewState::is_same_channel(&c.login, &state.channel))
-        // {
-        //     if channel.show_user_list {
-        //         SidePanel::right("user_list")
-        //             .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
-        //             .show_inside(ui, |ui| {
-        //                 UserList::new(&state.chatters, &self.state.state.images).display(ui);
-        //             });
-        //     }
-        // }
-
-        //     let show_timestamp = self
-        //     .state
-        //     .state
-        //     .channels
-        //     .iter()
-        //     .find_map(|c| (c.login == state.name()).then_some(c.show_timestamps))
-        //     .unwrap_or(true);
-
-        // ScrollArea::vertical()
-        //     .auto_shrink([false, false])
-        //     .stick_to_bottom(true) // TODO if we're scrolled up don't do this
-        //     .show(ui, |ui| {
-        //         for line in state.lines.iter() {
-        //             match line {
-        //                 Line::Chat(line) => {
-        //                     ChatLineView::new(
-        //                         line,
-        //                         &self.state.state.images,
-        //                         &self.state.state.emote_map,
-        //                         show_timestamp,
-        //                     )
-        //                     .display(ui);
-        //                 }
-        //             }
-        //         }
-        //     });
+        if resp.drag_released() {
+            let mut data = ui.data();
+            cvs.tab_bar_position = data
+                .get_temp(Id::new("tab_bar_drag_pos"))
+                .unwrap_or(cvs.tab_bar_position);
+            data.remove::<Position>(Id::new("tab_bar_drag_pos"));
+        }
     }
 }
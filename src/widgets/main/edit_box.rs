@@ -1,4 +1,11 @@
-use egui::TextEdit;
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use egui::{RichText, TextEdit};
+
+use crate::{helix::Kind, twitch::RoomSettings};
 
 // TODO spell check
 // TODO kappa completion
@@ -6,27 +13,129 @@ use egui::TextEdit;
 pub struct EditBox<'a> {
     buffer: &'a mut String,
     write: &'a flume::Sender<String>,
+    room: &'a RoomSettings,
+    sent: &'a mut VecDeque<Instant>,
+    last_sent: &'a mut Option<Instant>,
+    emotes: &'a [&'a str],
+    kind: Option<Kind>,
 }
 
 impl<'a> EditBox<'a> {
-    pub fn new(buffer: &'a mut String, write: &'a flume::Sender<String>) -> Self {
-        Self { buffer, write }
+    // twitch allows this many messages every 30 seconds, across every channel
+    const RATE_WINDOW: Duration = Duration::from_secs(30);
+    const RATE_LIMIT: usize = 20;
+    const MODERATOR_RATE_LIMIT: usize = 100;
+
+    pub fn new(
+        buffer: &'a mut String,
+        write: &'a flume::Sender<String>,
+        room: &'a RoomSettings,
+        sent: &'a mut VecDeque<Instant>,
+        last_sent: &'a mut Option<Instant>,
+        emotes: &'a [&'a str],
+        kind: Option<Kind>,
+    ) -> Self {
+        Self {
+            buffer,
+            write,
+            room,
+            sent,
+            last_sent,
+            emotes,
+            kind,
+        }
     }
 
-    pub fn display(self, ui: &mut egui::Ui) {
-        let id = self.buffer.as_ptr();
-        let resp = ui.add(
-            TextEdit::singleline(self.buffer)
-                .id_source(id)
-                .frame(false)
-                .lock_focus(true),
-        );
+    /// Returns the message that was sent
+    pub fn display(self, ui: &mut egui::Ui) -> Option<String> {
+        while matches!(self.sent.front(), Some(sent) if sent.elapsed() >= Self::RATE_WINDOW) {
+            self.sent.pop_front();
+        }
+
+        let wait = self.slow_mode_wait().max(self.rate_limit_wait());
+        let not_emotes = self.not_emotes();
+
+        ui.horizontal(|ui| {
+            if let Some(wait) = wait {
+                let secs = wait.as_secs() + 1;
+                ui.weak(format!("{secs}s")).on_hover_text_at_pointer(
+                    match self.room.slow.filter(|_| !self.is_exempt()) {
+                        Some(..) => "slow mode is on",
+                        None => "sending too fast",
+                    },
+                );
+                ui.ctx().request_repaint_after(Duration::from_secs(1));
+            }
+
+            if not_emotes {
+                ui.label(RichText::new("emotes only").color(ui.visuals().warn_fg_color))
+                    .on_hover_text_at_pointer(
+                        "this room is in emote-only mode, twitch may reject this message",
+                    );
+            }
+
+            let id = self.buffer.as_ptr();
+            let resp = ui.add_sized(
+                ui.available_size(),
+                TextEdit::singleline(self.buffer)
+                    .id_source(id)
+                    .frame(false)
+                    .lock_focus(true),
+            );
+            resp.request_focus();
+
+            if !resp.lost_focus()
+                || !ui.ctx().input().key_down(egui::Key::Enter)
+                || wait.is_some()
+                || self.buffer.trim().is_empty()
+            {
+                return None;
+            }
 
-        if resp.lost_focus() && ui.ctx().input().key_down(egui::Key::Enter) {
             let line = std::mem::take(self.buffer);
-            let _ = self.write.send(line);
+            let _ = self.write.send(line.clone());
+            let now = Instant::now();
+            self.sent.push_back(now);
+            self.last_sent.replace(now);
+            Some(line)
+        })
+        .inner
+    }
+
+    // moderators, vips and the broadcaster skip the chat modes
+    fn is_exempt(&self) -> bool {
+        matches!(
+            self.kind,
+            Some(Kind::Broadcaster | Kind::Moderator | Kind::Vip)
+        )
+    }
+
+    /// How long until slow mode lets us send another message
+    fn slow_mode_wait(&self) -> Option<Duration> {
+        let slow = Duration::from_secs(self.room.slow.filter(|_| !self.is_exempt())?);
+        slow.checked_sub(self.last_sent.as_ref()?.elapsed())
+            .filter(|wait| !wait.is_zero())
+    }
+
+    fn rate_limit_wait(&self) -> Option<Duration> {
+        let limit = match self.kind {
+            Some(Kind::Broadcaster | Kind::Moderator) => Self::MODERATOR_RATE_LIMIT,
+            _ => Self::RATE_LIMIT,
+        };
+
+        if self.sent.len() < limit {
+            return None;
         }
+        Self::RATE_WINDOW.checked_sub(self.sent.front()?.elapsed())
+    }
 
-        resp.request_focus();
+    // this only warns, we don't know every emote the user can send
+    fn not_emotes(&self) -> bool {
+        self.room.emote_only
+            && !self.is_exempt()
+            && self
+                .buffer
+                .split_ascii_whitespace()
+                .any(|word| !self.emotes.contains(&word))
     }
 }
@@ -7,7 +7,7 @@ use crate::state::AppState;
 use super::{ActiveSettingsView, SettingsView, StartView};
 
 mod chat_line;
-use chat_line::{ChatLine, ChatLineView};
+use chat_line::{ChatLine, ChatLineView, NoticeLine, NoticeLineView, SystemLine, SystemLineView};
 
 mod timestamp;
 use timestamp::Timestamp;
@@ -33,6 +33,21 @@ use user_list::UserList;
 mod edit_box;
 use edit_box::EditBox;
 
+mod channel_header;
+use channel_header::{ChannelHeader, HeaderAction};
+
+mod mod_log_view;
+use mod_log_view::ModLogView;
+
+mod moderation_menu;
+use moderation_menu::ModerationMenu;
+
+mod whisper_view;
+use whisper_view::WhisperView;
+
+mod user_card;
+use user_card::{UserCard, UserCardAction, UserCardView};
+
 #[derive(Copy, Clone, Default, Debug, PartialEq, PartialOrd, Eq, Ord, Hash)]
 pub enum MainView {
     #[default]
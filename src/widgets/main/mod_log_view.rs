@@ -0,0 +1,57 @@
+use egui::{Grid, Label, ScrollArea, TextEdit};
+
+use crate::{mod_log::ModLogEntry, Queue};
+
+use super::Timestamp;
+
+pub struct ModLogView<'a> {
+    entries: &'a Queue<(i64, ModLogEntry)>,
+    search: &'a mut String,
+}
+
+impl<'a> ModLogView<'a> {
+    pub fn new(entries: &'a Queue<(i64, ModLogEntry)>, search: &'a mut String) -> Self {
+        Self { entries, search }
+    }
+
+    pub fn display(self, ui: &mut egui::Ui) {
+        ui.add(
+            TextEdit::singleline(self.search)
+                .hint_text("search the mod log")
+                .desired_width(f32::INFINITY),
+        );
+        ui.separator();
+
+        let search = self.search.to_lowercase();
+        let entries = self
+            .entries
+            .iter()
+            .rev()
+            .map(|(at, entry)| (at, entry.to_string()))
+            .filter(|(_, text)| search.is_empty() || text.to_lowercase().contains(&search))
+            .collect::<Vec<_>>();
+
+        if entries.is_empty() {
+            ui.weak("nothing here");
+            return;
+        }
+
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                Grid::new("mod_log")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (at, text) in entries {
+                            match Timestamp::from_unix(*at) {
+                                Some(ts) => ui.small(ts.as_str()),
+                                None => ui.small(""),
+                            };
+                            ui.add(Label::new(text).wrap(true));
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+}
@@ -0,0 +1,79 @@
+use crate::moderation::ModAction;
+
+use super::ChatLine;
+
+/// The context menu for a chat line
+pub struct ModerationMenu<'a> {
+    line: &'a ChatLine,
+    is_moderator: bool,
+}
+
+impl<'a> ModerationMenu<'a> {
+    const TIMEOUTS: [(&'static str, u64); 4] = [
+        ("1 minute", 60),
+        ("10 minutes", 10 * 60),
+        ("1 hour", 60 * 60),
+        ("1 day", 24 * 60 * 60),
+    ];
+
+    pub const fn new(line: &'a ChatLine, is_moderator: bool) -> Self {
+        Self { line, is_moderator }
+    }
+
+    pub fn display(self, ui: &mut egui::Ui) -> Option<ModAction> {
+        // our local echo has a made up id
+        if ui
+            .add_enabled(!self.line.local, egui::Button::new("Copy message id"))
+            .clicked()
+        {
+            ui.output().copied_text = self.line.id.to_string();
+            ui.close_menu();
+        }
+
+        let login = match self.line.privmsg() {
+            Some(pm) if self.is_moderator => pm.sender.to_string(),
+            _ => return None,
+        };
+
+        ui.separator();
+
+        let mut action = None;
+        if ui
+            .add_enabled(
+                !self.line.deleted && !self.line.local,
+                egui::Button::new("Delete message"),
+            )
+            .clicked()
+        {
+            action.replace(ModAction::Delete {
+                login: login.clone(),
+                id: self.line.id,
+            });
+        }
+
+        ui.menu_button("Timeout", |ui| {
+            for (label, secs) in Self::TIMEOUTS {
+                if ui.button(label).clicked() {
+                    action.replace(ModAction::Timeout {
+                        login: login.clone(),
+                        secs,
+                    });
+                }
+            }
+        });
+
+        if ui.button("Ban").clicked() {
+            action.replace(ModAction::Ban {
+                login: login.clone(),
+            });
+        }
+        if ui.button("Unban").clicked() {
+            action.replace(ModAction::Unban { login });
+        }
+
+        if action.is_some() {
+            ui.close_menu();
+        }
+        action
+    }
+}
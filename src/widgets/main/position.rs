@@ -53,6 +53,18 @@ impl Position {
         ]
     }
 
+    /// The part of `max` that isn't covered by a tab bar at `tab_bar`
+    pub fn remaining(&self, max: Rect, tab_bar: Rect) -> Rect {
+        let mut rect = max;
+        match self {
+            Self::Top => rect.min.y = tab_bar.max.y,
+            Self::Bottom => rect.max.y = tab_bar.min.y,
+            Self::Left => rect.min.x = tab_bar.max.x,
+            Self::Right => rect.max.x = tab_bar.min.x,
+        }
+        rect
+    }
+
     pub const fn as_side(&self) -> Option<egui::panel::Side> {
         Some(match self {
             Self::Right => egui::panel::Side::Right,
@@ -1,41 +1,388 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use time::OffsetDateTime;
+
 use crate::{
-    helix::Chatters,
-    twitch::{self, EmoteSpan},
-    Queue,
+    helix::{Chatters, Kind, Stream},
+    mod_log::ModLogEntry,
+    twitch::{self, ChannelIdentity, EmoteSpan, RoomSettings},
+    whispers::Whispers,
+    FilterCounts, FilterReason, Highlight, Queue,
 };
 
-use super::{ChatLine, Position, Timestamp};
+use super::{ChatLine, NoticeLine, Position, SystemLine, Timestamp, UserCard};
 
 #[derive(Default)]
 pub struct EditBuffer {
     pub buffer: String,
 }
 
+/// A highlighted line, and the channel it came from
+pub struct Mention {
+    pub channel: String,
+    pub line: ChatLine,
+}
+
 pub enum Line {
     Chat(ChatLine),
+    System(SystemLine),
+    Notice(NoticeLine),
+}
+
+impl Line {
+    pub fn as_chat(&self) -> Option<&ChatLine> {
+        match self {
+            Self::Chat(line) => Some(line),
+            _ => None,
+        }
+    }
+
+    pub const fn ts(&self) -> &Timestamp {
+        match self {
+            Self::Chat(ChatLine { ts, .. })
+            | Self::System(SystemLine { ts, .. })
+            | Self::Notice(NoticeLine { ts, .. }) => ts,
+        }
+    }
+
+    /// The chat line, or the message attached to a notice
+    pub fn message(&self) -> Option<&ChatLine> {
+        match self {
+            Self::Chat(line) => Some(line),
+            Self::Notice(notice) => notice.message.as_ref(),
+            _ => None,
+        }
+    }
+
+    fn message_mut(&mut self) -> Option<&mut ChatLine> {
+        match self {
+            Self::Chat(line) => Some(line),
+            Self::Notice(notice) => notice.message.as_mut(),
+            _ => None,
+        }
+    }
 }
 
 pub struct ChannelState {
-    chatters: Chatters,
-    buffer: EditBuffer,
-    lines: Queue<Line>,
-    channel: String,
+    pub(super) chatters: Chatters,
+    pub(super) buffer: EditBuffer,
+    pub(super) lines: Queue<Line>,
+    // lines kept out of chat by the filters
+    pub(super) filtered: Queue<Line>,
+    pub(super) filter_counts: FilterCounts,
+    pub(super) show_filtered: bool,
+    // a line to scroll to, from the mentions tab
+    pub(super) scroll_to: Option<uuid::Uuid>,
+    // lines that arrived while the tab wasn't shown
+    unread: usize,
+    unread_mentions: usize,
+    // when the last line we saw arrived, the marker is drawn after it
+    pub(super) last_read: Option<OffsetDateTime>,
+    pub(super) scroll_to_last_read: bool,
+    pub(super) channel: String,
+    pub(super) stream: Option<Stream>,
+    stream_polled: bool,
+    pub(super) room: RoomSettings,
+    // the first ROOMSTATE has every mode, so it isn't a change
+    room_known: bool,
+    pub(super) mod_log: Queue<(i64, ModLogEntry)>,
+    pub(super) mod_log_search: String,
+    pub(super) show_mod_log: bool,
+    pub(super) identity: Option<ChannelIdentity>,
+    // when we last sent a message here, for slow mode
+    pub(super) last_sent: Option<Instant>,
+    // when we last saw each chatter on irc
+    seen: HashMap<String, Instant>,
+    seen_pruned: Instant,
 }
 
 impl ChannelState {
-    pub fn chatters_mut(&mut self) -> &mut Chatters {
-        &mut self.chatters
+    // helix lags behind irc, so recently seen chatters survive a refresh they're missing from
+    const SEEN_GRACE: Duration = Duration::from_secs(2 * 60);
+    const MOD_LOG_HISTORY: usize = 500;
+
+    /// Merges a full list of chatters from Helix
+    ///
+    /// Helix doesn't know roles, so the ones learned from badges are kept
+    pub fn merge_chatters(&mut self, fresh: Chatters) {
+        let mut old = std::mem::take(&mut self.chatters);
+
+        for (kind, name) in fresh {
+            let kind = old.remove(&name).map_or(kind, |old| old.min(kind));
+            self.chatters.insert(kind, name);
+        }
+
+        self.prune_seen();
+        for (kind, name) in old {
+            if self.seen.contains_key(&name) {
+                self.chatters.insert(kind, name);
+            }
+        }
+    }
+
+    /// Notes that the user is in the channel
+    ///
+    /// `kind` is from their badges, if we saw them talk
+    pub fn user_seen(&mut self, name: &str, kind: Option<Kind>) {
+        self.seen.insert(name.to_string(), Instant::now());
+        // without helix nothing else would ever shrink it
+        if self.seen_pruned.elapsed() >= Self::SEEN_GRACE {
+            self.prune_seen();
+        }
+
+        let kind = match (kind, self.chatters.get(name)) {
+            (Some(kind), _) => kind,
+            (None, Some(..)) => return,
+            (None, None) => Kind::Viewer,
+        };
+
+        if self.chatters.get(name) != Some(kind) {
+            self.chatters.insert(kind, name);
+        }
+    }
+
+    fn prune_seen(&mut self) {
+        self.seen
+            .retain(|_, seen| seen.elapsed() < Self::SEEN_GRACE);
+        self.seen_pruned = Instant::now();
+    }
+
+    pub fn user_left(&mut self, name: &str) {
+        self.seen.remove(name);
+        self.chatters.remove(name);
     }
 
     pub fn name(&self) -> &str {
         &self.channel
     }
 
+    pub fn stream(&self) -> Option<&Stream> {
+        self.stream.as_ref()
+    }
+
+    /// Whether the stream has been polled since we joined
+    pub const fn has_polled_stream(&self) -> bool {
+        self.stream_polled
+    }
+
+    pub fn set_identity(&mut self, identity: ChannelIdentity) {
+        self.identity.replace(identity);
+    }
+
+    /// Our role here, from USERSTATE or else from the list of chatters
+    pub fn our_kind(&self, identity: Option<&twitch::Identity>) -> Option<Kind> {
+        self.identity
+            .as_ref()
+            .map(|id| id.kind)
+            .or_else(|| identity.and_then(|id| self.chatters.get(&id.user_name.to_lowercase())))
+    }
+
+    /// Returns the modes that changed
+    pub fn room_state(&mut self, state: &twitch::RoomState<'_>) -> Vec<ModLogEntry> {
+        let old = self.room;
+        state.apply(&mut self.room);
+        if !std::mem::replace(&mut self.room_known, true) {
+            return vec![];
+        }
+        ModLogEntry::diff_room(&old, &self.room)
+    }
+
+    /// Adds the entry to the mod log, and stores it
+    pub fn log_mod(&mut self, entry: ModLogEntry) {
+        let at = time::OffsetDateTime::now_utc().unix_timestamp();
+        if !entry.store(&self.channel, at) {
+            log::warn!("cannot store mod log entry for {}", self.channel);
+        }
+        self.mod_log.push((at, entry));
+    }
+
+    /// Loads the stored mod log since the unix time
+    pub fn load_mod_log(&mut self, since: i64) {
+        self.mod_log = Queue::with_capacity(Self::MOD_LOG_HISTORY);
+        for entry in ModLogEntry::since(&self.channel, since) {
+            self.mod_log.push(entry);
+        }
+    }
+
+    pub fn set_stream(&mut self, stream: Option<Stream>) {
+        self.stream = stream;
+        self.stream_polled = true;
+    }
+
+    pub fn push_notice(
+        &mut self,
+        kind: twitch::UserNoticeKind,
+        system_msg: String,
+        message: Option<(uuid::Uuid, Vec<EmoteSpan>)>,
+        msg: twitch::Message,
+        highlight: Option<Highlight>,
+        filtered: Option<FilterReason>,
+    ) {
+        let ts = Timestamp::now_local();
+        let message = message.map(|(id, spans)| ChatLine {
+            ts: ts.clone(),
+            id,
+            spans,
+            msg,
+            deleted: false,
+            highlight,
+            local: false,
+        });
+
+        let line = Line::Notice(NoticeLine {
+            ts,
+            kind,
+            system_msg,
+            message,
+        });
+        self.push_line(line, filtered)
+    }
+
+    pub fn push_system(&mut self, text: impl ToString) {
+        let ts = Timestamp::now_local();
+        let text = text.to_string();
+        self.lines.push(Line::System(SystemLine { ts, text }))
+    }
+
+    /// Pushes a system line for something that happened at the unix time
+    pub fn push_system_at(&mut self, at: i64, text: impl ToString) {
+        if let Some(ts) = Timestamp::from_unix(at) {
+            let text = text.to_string();
+            self.lines.push(Line::System(SystemLine { ts, text }))
+        }
+    }
+
     // TODO do we really need the full message?
     // if we make an owned variant of Privmsg we can just store that
-    pub fn push_privmsg(&mut self, id: uuid::Uuid, spans: Vec<EmoteSpan>, msg: twitch::Message) {
+    pub fn push_privmsg(
+        &mut self,
+        id: uuid::Uuid,
+        spans: Vec<EmoteSpan>,
+        msg: twitch::Message,
+        highlight: Option<Highlight>,
+        filtered: Option<FilterReason>,
+    ) {
         let ts = Timestamp::now_local();
-        self.lines.push(Line::Chat(ChatLine { ts, id, spans, msg }))
+        let line = Line::Chat(ChatLine {
+            ts,
+            id,
+            spans,
+            msg,
+            deleted: false,
+            highlight,
+            local: false,
+        });
+        self.push_line(line, filtered)
+    }
+
+    /// Shows a message we sent, see [`twitch::Message::local_echo`]
+    pub fn push_local_echo(&mut self, id: uuid::Uuid, spans: Vec<EmoteSpan>, msg: twitch::Message) {
+        let line = Line::Chat(ChatLine {
+            ts: Timestamp::now_local(),
+            id,
+            spans,
+            msg,
+            deleted: false,
+            highlight: None,
+            local: true,
+        });
+        self.push_line(line, None)
+    }
+
+    fn push_line(&mut self, line: Line, filtered: Option<FilterReason>) {
+        if let Some(reason) = filtered {
+            self.filter_counts.add(reason);
+            self.filtered.push(line);
+            return;
+        }
+
+        self.unread += 1;
+        // only the highlights that go to the mentions tab, like our name being said
+        if line
+            .message()
+            .and_then(|line| line.highlight)
+            .is_some_and(|highlight| highlight.copy_to_mentions)
+        {
+            self.unread_mentions += 1;
+        }
+        self.lines.push(line)
+    }
+
+    pub const fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub const fn unread_mentions(&self) -> usize {
+        self.unread_mentions
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = 0;
+        self.unread_mentions = 0;
+    }
+
+    /// Remembers the newest line, so a marker can be drawn after it
+    fn set_last_read(&mut self) {
+        self.last_read = self.lines.iter().last().map(|line| line.ts().date_time);
+    }
+
+    pub fn jump_to_last_read(&mut self) {
+        self.scroll_to_last_read = self.last_read.is_some();
+    }
+
+    /// The lines to show, and whether each was filtered
+    ///
+    /// Filtered lines are merged in by time when they're shown
+    pub fn visible_lines(&self) -> impl Iterator<Item = (&Line, bool)> + '_ {
+        let mut lines = self.lines.iter().peekable();
+        let mut filtered = self
+            .show_filtered
+            .then(|| self.filtered.iter())
+            .into_iter()
+            .flatten()
+            .peekable();
+
+        std::iter::from_fn(move || match (lines.peek(), filtered.peek()) {
+            (Some(left), Some(right)) if right.ts().date_time < left.ts().date_time => {
+                filtered.next().map(|line| (line, true))
+            }
+            (Some(_), _) => lines.next().map(|line| (line, false)),
+            (None, _) => filtered.next().map(|line| (line, true)),
+        })
+    }
+
+    /// Marks the user's messages as deleted, or every message if there is no user
+    pub fn clear_chat(&mut self, user: Option<&str>) {
+        for line in self
+            .lines
+            .iter_mut()
+            .chain(self.filtered.iter_mut())
+            .filter_map(Line::message_mut)
+        {
+            let matches = user.is_none_or(|user| {
+                line.privmsg()
+                    .filter(|pm| pm.sender.eq_ignore_ascii_case(user))
+                    .is_some()
+            });
+            if matches {
+                line.deleted = true;
+            }
+        }
+    }
+
+    pub fn clear_message(&mut self, id: uuid::Uuid) {
+        if let Some(line) = self
+            .lines
+            .iter_mut()
+            .chain(self.filtered.iter_mut())
+            .filter_map(Line::message_mut)
+            .find(|line| line.id == id)
+        {
+            line.deleted = true;
+        }
     }
 }
 
@@ -43,10 +390,21 @@ pub struct ChatViewState {
     pub channels: Vec<ChannelState>, // BUG what is this
     pub active: Option<usize>,
     pub tab_bar_hidden: bool,
+    // the channel shown in a focused window last frame
+    pub shown: Option<String>,
 
     pub tab_bar_position: Position,
     pub image_size: f32,
     pub show_mask: bool,
+
+    pub user_card: Option<UserCard>,
+
+    pub whispers: Whispers,
+    pub show_whispers: bool,
+
+    // highlighted lines from every channel
+    pub mentions: Queue<Mention>,
+    pub show_mentions: bool,
 }
 
 impl Default for ChatViewState {
@@ -55,14 +413,22 @@ impl Default for ChatViewState {
             channels: Vec::new(),
             active: None,
             tab_bar_hidden: false,
+            shown: None,
             image_size: 32.0,
             tab_bar_position: Position::Top,
             show_mask: false,
+            user_card: None,
+            whispers: Whispers::default(),
+            show_whispers: false,
+            mentions: Queue::with_capacity(Self::MENTIONS_HISTORY),
+            show_mentions: false,
         }
     }
 }
 
 impl ChatViewState {
+    const MENTIONS_HISTORY: usize = 200;
+
     pub const fn active_index(&self) -> Option<usize> {
         self.active
     }
@@ -72,19 +438,118 @@ impl ChatViewState {
             return;
         }
         self.active.replace(index);
+        self.show_whispers = false;
+        self.show_mentions = false;
+    }
+
+    /// Tracks which channel is being looked at.
+    ///
+    /// The previous one remembers its last line when it stops being shown
+    pub fn update_shown(&mut self, focused: bool) {
+        let shown = (focused && !self.show_whispers && !self.show_mentions)
+            .then(|| self.active())
+            .flatten()
+            .map(|state| state.name().to_string());
+        if shown == self.shown {
+            return;
+        }
+
+        let previous = std::mem::replace(&mut self.shown, shown);
+        if let Some(state) = previous.and_then(|name| self.get_mut_by_name(&name)) {
+            state.set_last_read();
+        }
+    }
+
+    /// Unread messages and mentions across every channel
+    pub fn unread(&self) -> (usize, usize) {
+        self.channels
+            .iter()
+            .fold((0, 0), |(messages, mentions), state| {
+                (
+                    messages + state.unread(),
+                    mentions + state.unread_mentions(),
+                )
+            })
+    }
+
+    /// Copies the line from the channel to the mentions tab
+    pub fn push_mention(&mut self, channel: &str, id: uuid::Uuid) {
+        let line = self
+            .get_by_name(channel)
+            .and_then(|state| {
+                state
+                    .lines
+                    .iter()
+                    .rev()
+                    .filter_map(Line::message)
+                    .find(|line| line.id == id)
+            })
+            .cloned();
+
+        if let Some(line) = line {
+            let channel = channel.to_string();
+            self.mentions.push(Mention { channel, line })
+        }
+    }
+
+    /// Marks the mentions from the user as deleted, or all of the channel's if there is no user
+    pub fn clear_mentions(&mut self, channel: &str, user: Option<&str>) {
+        for mention in self
+            .mentions
+            .iter_mut()
+            .filter(|mention| Self::is_same_channel(&mention.channel, channel))
+        {
+            let matches = user.is_none_or(|user| {
+                mention
+                    .line
+                    .privmsg()
+                    .filter(|pm| pm.sender.eq_ignore_ascii_case(user))
+                    .is_some()
+            });
+            if matches {
+                mention.line.deleted = true;
+            }
+        }
+    }
+
+    pub fn clear_mention(&mut self, id: uuid::Uuid) {
+        if let Some(mention) = self.mentions.iter_mut().find(|m| m.line.id == id) {
+            mention.line.deleted = true;
+        }
+    }
+
+    /// Switches to the channel, and scrolls to the line if it's still there
+    pub fn jump_to(&mut self, channel: &str, id: uuid::Uuid) {
+        let index = match self
+            .channels
+            .iter()
+            .position(|state| Self::is_same_channel(state.name(), channel))
+        {
+            Some(index) => index,
+            None => return,
+        };
+
+        self.set_active(index);
+        self.channels[index].scroll_to.replace(id);
     }
 
     pub fn get_mut(&mut self, index: usize) -> Option<&mut ChannelState> {
         self.channels.get_mut(index)
     }
 
+    pub fn get_by_name(&self, name: &str) -> Option<&ChannelState> {
+        self.channels
+            .iter()
+            .find(|ch| Self::is_same_channel(&ch.channel, name))
+    }
+
     pub fn get_mut_by_name(&mut self, name: &str) -> Option<&mut ChannelState> {
         self.channels
             .iter_mut()
-            .find(|ch| Self::is_same_channel(&*ch.channel, name))
+            .find(|ch| Self::is_same_channel(&ch.channel, name))
     }
 
-    fn is_same_channel(left: &str, right: &str) -> bool {
+    pub fn is_same_channel(left: &str, right: &str) -> bool {
         left.strip_prefix('#').unwrap_or(left) == right.strip_prefix('#').unwrap_or(right)
     }
 
@@ -102,6 +567,8 @@ impl ChatViewState {
             None => return,
         };
         *active = (*active + 1) % self.channels.len();
+        self.show_whispers = false;
+        self.show_mentions = false;
     }
 
     pub fn previous(&mut self) {
@@ -110,10 +577,13 @@ impl ChatViewState {
             None => return,
         };
 
-        *active = (*active == 0)
-            .then_some(self.channels.len())
-            .unwrap_or(*active)
-            - 1;
+        *active = if *active == 0 {
+            self.channels.len()
+        } else {
+            *active
+        } - 1;
+        self.show_whispers = false;
+        self.show_mentions = false;
     }
 
     pub fn add_channel(&mut self, channel: impl ToString) {
@@ -121,7 +591,26 @@ impl ChatViewState {
             chatters: Chatters::default(),
             buffer: EditBuffer::default(),
             lines: Queue::default(),
+            filtered: Queue::default(),
+            filter_counts: FilterCounts::default(),
+            show_filtered: false,
+            scroll_to: None,
+            unread: 0,
+            unread_mentions: 0,
+            last_read: None,
+            scroll_to_last_read: false,
             channel: channel.to_string(),
+            stream: None,
+            stream_polled: false,
+            room: RoomSettings::default(),
+            room_known: false,
+            mod_log: Queue::with_capacity(ChannelState::MOD_LOG_HISTORY),
+            mod_log_search: String::new(),
+            show_mod_log: false,
+            identity: None,
+            last_sent: None,
+            seen: HashMap::new(),
+            seen_pruned: Instant::now(),
         });
         self.set_active(self.channels.len() - 1);
     }
@@ -137,3 +626,118 @@ impl ChatViewState {
         self.tab_bar_hidden = !self.tab_bar_hidden;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel() -> ChannelState {
+        let mut state = ChatViewState::default();
+        state.add_channel("#test");
+        state.channels.pop().unwrap()
+    }
+
+    #[test]
+    fn merge_chatters() {
+        let mut channel = channel();
+        channel.user_seen("mod", Some(Kind::Moderator));
+        channel.user_seen("talker", None);
+        channel.chatters.insert(Kind::Viewer, "gone");
+
+        let fresh = ["mod", "lurker"]
+            .into_iter()
+            .map(|name| (Kind::Viewer, name.to_string()))
+            .collect();
+        channel.merge_chatters(fresh);
+
+        // roles from badges survive, and so do chatters helix hasn't caught up with
+        assert_eq!(channel.chatters.get("mod"), Some(Kind::Moderator));
+        assert_eq!(channel.chatters.get("lurker"), Some(Kind::Viewer));
+        assert_eq!(channel.chatters.get("talker"), Some(Kind::Viewer));
+        assert_eq!(channel.chatters.get("gone"), None);
+    }
+
+    #[test]
+    fn visible_lines() {
+        let line = |secs, text: &str| {
+            Line::System(SystemLine {
+                ts: Timestamp::from_unix(secs).unwrap(),
+                text: text.to_string(),
+            })
+        };
+
+        let mut channel = channel();
+        channel.lines.push(line(1, "a"));
+        channel.lines.push(line(3, "c"));
+        channel.lines.push(line(4, "d"));
+        channel.filtered.push(line(2, "b"));
+        channel.filtered.push(line(4, "e"));
+        channel.filtered.push(line(5, "f"));
+
+        let visible = |channel: &ChannelState| {
+            channel
+                .visible_lines()
+                .map(|(line, filtered)| match line {
+                    Line::System(line) => (line.text.clone(), filtered),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let expect = |lines: &[(&str, bool)]| {
+            lines
+                .iter()
+                .map(|(text, filtered)| (text.to_string(), *filtered))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            visible(&channel),
+            expect(&[("a", false), ("c", false), ("d", false)])
+        );
+
+        // shown lines come first when the times are the same
+        channel.show_filtered = true;
+        assert_eq!(
+            visible(&channel),
+            expect(&[
+                ("a", false),
+                ("b", true),
+                ("c", false),
+                ("d", false),
+                ("e", true),
+                ("f", true),
+            ])
+        );
+    }
+
+    #[test]
+    fn unread_mentions() {
+        let mut channel = channel();
+        // `push_privmsg` uses the local time, which can't be read with other threads running
+        let push = |channel: &mut ChannelState, highlight| {
+            let raw = "@id=f5b1e6a2-3f0c-4e4b-9a52-6f1d2c9b8a11 :bob!bob@bob.tmi.twitch.tv PRIVMSG #test :hi";
+            let msg = twitch::Message::parse(raw).unwrap();
+            let line = Line::Chat(ChatLine {
+                ts: Timestamp::from_unix(0).unwrap(),
+                id: msg.as_privmsg().unwrap().id(),
+                spans: vec![],
+                msg,
+                deleted: false,
+                highlight,
+                local: false,
+            });
+            channel.push_line(line, None);
+        };
+
+        let rule = Highlight {
+            copy_to_mentions: false,
+            ..Highlight::MENTION
+        };
+        push(&mut channel, None);
+        push(&mut channel, Some(rule));
+        push(&mut channel, Some(Highlight::MENTION));
+
+        assert_eq!(channel.unread(), 3);
+        assert_eq!(channel.unread_mentions(), 1);
+    }
+}
@@ -1,10 +1,10 @@
 use egui::{
-    pos2, vec2, Color32, CursorIcon, Id, Label, PointerButton, Rect, Response, Rounding, Sense,
-    Stroke, Vec2,
+    pos2, vec2, Align2, Color32, CursorIcon, FontId, Id, Label, PointerButton, Pos2, Rect,
+    Response, RichText, Rounding, SelectableLabel, Sense, Stroke, Vec2,
 };
 use egui_extras::RetainedImage;
 
-use crate::{fetch::ImageKind, store::Image, Channel, FetchQueue, ImageCache};
+use crate::{fetch::ImageKind, font_icon, store::Image, Channel, FetchQueue, ImageCache};
 
 use super::{ChatViewState, Position};
 
@@ -36,7 +36,7 @@ impl<'a> TabView<'a> {
         }
     }
 
-    pub fn display(self, ui: &mut egui::Ui) {
+    pub fn display(mut self, ui: &mut egui::Ui) {
         for (i, channel) in self.channels.iter().enumerate() {
             let img = match self.images.get_id(channel.image_id) {
                 Some(img) => img,
@@ -69,6 +69,44 @@ impl<'a> TabView<'a> {
                 });
             }
 
+            let channel_state = self.state.get_by_name(&channel.login);
+            let stream = channel_state.and_then(|state| state.stream());
+            let (unread, mentions) = channel_state
+                .map(|state| (state.unread(), state.unread_mentions()))
+                .unwrap_or_default();
+
+            if stream.is_some() {
+                ui.painter().text(
+                    resp.rect.right_top(),
+                    Align2::RIGHT_TOP,
+                    font_icon::LIVE,
+                    FontId::proportional(self.state.image_size * 0.3),
+                    Color32::RED,
+                );
+            }
+
+            let font = FontId::proportional(self.state.image_size * 0.3);
+            if unread > 0 {
+                Self::paint_count(
+                    ui,
+                    resp.rect.right_bottom(),
+                    Align2::RIGHT_BOTTOM,
+                    unread,
+                    font.clone(),
+                    Color32::WHITE,
+                );
+            }
+            if mentions > 0 {
+                Self::paint_count(
+                    ui,
+                    resp.rect.left_top(),
+                    Align2::LEFT_TOP,
+                    mentions,
+                    font,
+                    crate::TWITCH_COLOR,
+                );
+            }
+
             if resp.hovered() && !resp.dragged() {
                 ui.painter().rect(
                     resp.rect,
@@ -81,6 +119,12 @@ impl<'a> TabView<'a> {
             let resp = resp.on_hover_ui_at_pointer(|ui| {
                 ui.vertical(|ui| {
                     ui.monospace(&channel.display_name);
+                    if let Some(stream) = stream {
+                        ui.label(&stream.title);
+                    }
+                    if unread > 0 {
+                        ui.small(format!("{unread} unread, {mentions} mentions"));
+                    }
 
                     if ui.ctx().input().modifiers.shift {
                         if !channel.description.is_empty() {
@@ -107,9 +151,14 @@ impl<'a> TabView<'a> {
 
             if resp.clicked() {
                 self.state.active.replace(i);
+                self.state.show_whispers = false;
+                self.state.show_mentions = false;
             }
         }
 
+        self.display_whispers_tab(ui);
+        self.display_mentions_tab(ui);
+
         if !ui.input().modifiers.command_only() {
             return;
         }
@@ -159,22 +208,25 @@ impl<'a> TabView<'a> {
 
         let Vec2 { x, y } = ui.spacing().item_spacing;
         let spacing = self.state.image_size
-            + self
-                .state
-                .tab_bar_position
-                .is_horizontal()
-                .then_some(x)
-                .unwrap_or(y);
+            + if self.state.tab_bar_position.is_horizontal() {
+                x
+            } else {
+                y
+            };
 
         let x = line.left()
-            - matches!(self.state.tab_bar_position, Position::Right)
-                .then_some(self.state.image_size)
-                .unwrap_or(0.0);
+            - if matches!(self.state.tab_bar_position, Position::Right) {
+                self.state.image_size
+            } else {
+                0.0
+            };
 
         let y = line.top()
-            - matches!(self.state.tab_bar_position, Position::Bottom)
-                .then_some(self.state.image_size)
-                .unwrap_or(0.0);
+            - if matches!(self.state.tab_bar_position, Position::Bottom) {
+                self.state.image_size
+            } else {
+                0.0
+            };
 
         for i in 0..self.channels.len() {
             if i == id {
@@ -229,4 +281,75 @@ impl<'a> TabView<'a> {
             ui.data().insert_temp(Id::new("drag_tab_target"), i);
         }
     }
+
+    /// Draws a count on a dark background, so it can be read over the avatar
+    fn paint_count(
+        ui: &egui::Ui,
+        pos: Pos2,
+        align: Align2,
+        count: usize,
+        font: FontId,
+        color: Color32,
+    ) {
+        let text = match count {
+            0..=99 => count.to_string(),
+            _ => "99+".to_string(),
+        };
+
+        let galley = ui.painter().layout_no_wrap(text, font, color);
+        let rect = align.anchor_rect(Rect::from_min_size(pos, galley.size()));
+        ui.painter().rect_filled(
+            rect.expand(1.0),
+            Rounding::same(2.0),
+            Color32::from_black_alpha(0xC0),
+        );
+        ui.painter().galley(rect.min, galley);
+    }
+
+    fn display_whispers_tab(&mut self, ui: &mut egui::Ui) {
+        let size = self.state.image_size;
+        let resp = ui
+            .add_sized(
+                vec2(size, size),
+                SelectableLabel::new(
+                    self.state.show_whispers,
+                    RichText::new(font_icon::WHISPER).size(size * 0.6),
+                ),
+            )
+            .on_hover_text_at_pointer("whispers");
+
+        let unread = self.state.whispers.unread();
+        if unread > 0 {
+            ui.painter().text(
+                resp.rect.right_top(),
+                Align2::RIGHT_TOP,
+                unread.to_string(),
+                FontId::proportional(size * 0.3),
+                crate::TWITCH_COLOR,
+            );
+        }
+
+        if resp.clicked() {
+            self.state.show_whispers = !self.state.show_whispers;
+            self.state.show_mentions = false;
+        }
+    }
+
+    fn display_mentions_tab(&mut self, ui: &mut egui::Ui) {
+        let size = self.state.image_size;
+        let resp = ui
+            .add_sized(
+                vec2(size, size),
+                SelectableLabel::new(
+                    self.state.show_mentions,
+                    RichText::new(font_icon::MENTIONS).size(size * 0.6),
+                ),
+            )
+            .on_hover_text_at_pointer("mentions and highlights");
+
+        if resp.clicked() {
+            self.state.show_mentions = !self.state.show_mentions;
+            self.state.show_whispers = false;
+        }
+    }
 }
@@ -13,6 +13,16 @@ impl Timestamp {
         Self { date_time, repr }
     }
 
+    /// A timestamp for the unix time, in the local offset
+    pub fn from_unix(secs: i64) -> Option<Self> {
+        let offset = time::UtcOffset::current_local_offset().unwrap_or(time::UtcOffset::UTC);
+        let date_time = OffsetDateTime::from_unix_timestamp(secs)
+            .ok()?
+            .to_offset(offset);
+        let repr = date_time.format(&crate::FORMAT).ok()?;
+        Some(Self { date_time, repr })
+    }
+
     pub fn as_str(&self) -> &str {
         &self.repr
     }
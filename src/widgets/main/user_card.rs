@@ -0,0 +1,263 @@
+use egui::{vec2, Grid, Label, RichText, ScrollArea, Vec2, Window};
+use poll_promise::Promise;
+
+use crate::{
+    fetch::ImageKind,
+    helix::{self, IdOrLogin},
+    store::Image,
+    ChannelAssets, FetchQueue, ImageCache, Queue,
+};
+
+use super::{state::Line, ChatLineView};
+
+pub enum UserCardAction {
+    Mention(String),
+    Whisper(String),
+    Ignore(String),
+    Timeout(String, u64),
+    Ban(String),
+    Close,
+}
+
+/// An open user card, for a user in a channel
+pub struct UserCard {
+    pub login: String,
+    pub channel: String,
+    user: Option<Promise<helix::Result<helix::User>>>,
+}
+
+impl UserCard {
+    pub fn open(helix: Option<&helix::Client>, login: &str, channel: &str) -> Self {
+        let user = helix.cloned().map(|helix| {
+            let login = login.to_string();
+            Promise::spawn_thread("user_card", move || {
+                helix
+                    .get_users([IdOrLogin::Login(&login)])?
+                    .pop()
+                    .ok_or_else(|| helix::Error::NotFound(format!("unknown user: {login}")))
+            })
+        });
+
+        Self {
+            login: login.to_string(),
+            channel: channel.to_string(),
+            user,
+        }
+    }
+}
+
+pub struct UserCardView<'a> {
+    card: &'a UserCard,
+    lines: &'a Queue<Line>,
+    cache: &'a mut ImageCache,
+    fetch: &'a mut FetchQueue<Image>,
+    assets: Option<&'a ChannelAssets>,
+    global_badges: &'a [helix::Badges],
+    is_moderator: bool,
+}
+
+impl<'a> UserCardView<'a> {
+    const AVATAR_SIZE: Vec2 = vec2(64.0, 64.0);
+    const BADGE_SIZE: Vec2 = vec2(18.0, 18.0);
+    const RECENT_MESSAGES: usize = 20;
+    const TIMEOUT: u64 = 10 * 60;
+
+    pub fn new(
+        card: &'a UserCard,
+        lines: &'a Queue<Line>,
+        cache: &'a mut ImageCache,
+        fetch: &'a mut FetchQueue<Image>,
+        assets: Option<&'a ChannelAssets>,
+        global_badges: &'a [helix::Badges],
+        is_moderator: bool,
+    ) -> Self {
+        Self {
+            card,
+            lines,
+            cache,
+            fetch,
+            assets,
+            global_badges,
+            is_moderator,
+        }
+    }
+
+    pub fn display(self, ctx: &egui::Context) -> Option<UserCardAction> {
+        let mut open = true;
+        let mut action = None;
+
+        Window::new(&self.card.login)
+            .id(egui::Id::new(("user_card", &self.card.login)))
+            .collapsible(false)
+            .resizable(true)
+            .default_width(300.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                action = self.display_card(ui);
+            });
+
+        (!open).then_some(UserCardAction::Close).or(action)
+    }
+
+    fn display_card(self, ui: &mut egui::Ui) -> Option<UserCardAction> {
+        let login = &self.card.login;
+
+        ui.horizontal(
+            |ui| match self.card.user.as_ref().map(|user| user.ready()) {
+                Some(Some(Ok(user))) => {
+                    let id = self.cache.id_for_url(&user.profile_image_url);
+                    match self.cache.get_id(id) {
+                        Some(img) => {
+                            img.show_max_size(ui, Self::AVATAR_SIZE);
+                        }
+                        None => {
+                            self.fetch.fetch(Image {
+                                id,
+                                url: user.profile_image_url.clone(),
+                                kind: ImageKind::Display,
+                                meta: (),
+                            });
+                            ui.allocate_exact_size(Self::AVATAR_SIZE, egui::Sense::hover());
+                        }
+                    }
+
+                    ui.vertical(|ui| {
+                        ui.heading(&user.display_name);
+                        ui.small(format!("created {}", Self::format_date(&user.created_at)));
+                    });
+                }
+                Some(Some(Err(err))) => {
+                    ui.heading(login);
+                    ui.colored_label(ui.visuals().error_fg_color, err.to_string());
+                }
+                Some(None) => {
+                    ui.heading(login);
+                    ui.spinner();
+                }
+                None => {
+                    ui.heading(login);
+                }
+            },
+        );
+
+        if let Some(Some(Ok(user))) = self.card.user.as_ref().map(|user| user.ready()) {
+            if !user.description.is_empty() {
+                ui.add(Label::new(RichText::new(&user.description).italics()).wrap(true));
+            }
+        }
+
+        let recent = self
+            .lines
+            .iter()
+            .rev()
+            .filter_map(Line::as_chat)
+            .filter(|line| {
+                line.msg
+                    .as_privmsg()
+                    .filter(|pm| pm.sender == login)
+                    .is_some()
+            })
+            .take(Self::RECENT_MESSAGES)
+            .collect::<Vec<_>>();
+
+        let mut badges = Vec::<(&str, &str)>::new();
+        for pm in recent.iter().filter_map(|line| line.msg.as_privmsg()) {
+            for badge in pm.badges() {
+                if !badges.contains(&badge) {
+                    badges.push(badge)
+                }
+            }
+        }
+
+        if !badges.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                for (set_id, id) in badges {
+                    let version = match self
+                        .assets
+                        .and_then(|assets| assets.find_badge(set_id, id))
+                        .or_else(|| helix::Badges::find(self.global_badges, set_id, id))
+                    {
+                        Some(version) => version,
+                        None => continue,
+                    };
+
+                    if let Some(resp) = ChatLineView::show_image(
+                        self.cache,
+                        self.fetch,
+                        &version.image_url_2x,
+                        ImageKind::Badge,
+                        Self::BADGE_SIZE,
+                        ui,
+                    ) {
+                        resp.on_hover_text_at_pointer(set_id);
+                    }
+                }
+            });
+        }
+
+        ui.separator();
+
+        let mut action = None;
+        ui.horizontal_wrapped(|ui| {
+            if ui.button("Mention").clicked() {
+                action.replace(UserCardAction::Mention(login.clone()));
+            }
+            if ui.button("Whisper").clicked() {
+                action.replace(UserCardAction::Whisper(login.clone()));
+            }
+            if ui.button("Ignore").clicked() {
+                action.replace(UserCardAction::Ignore(login.clone()));
+            }
+
+            if self.is_moderator {
+                ui.separator();
+                if ui
+                    .button("Timeout")
+                    .on_hover_text_at_pointer("Time out for 10 minutes")
+                    .clicked()
+                {
+                    action.replace(UserCardAction::Timeout(login.clone(), Self::TIMEOUT));
+                }
+                if ui.button("Ban").clicked() {
+                    action.replace(UserCardAction::Ban(login.clone()));
+                }
+            }
+        });
+
+        ui.separator();
+
+        if recent.is_empty() {
+            ui.weak(format!("no recent messages in {}", self.card.channel));
+            return action;
+        }
+
+        ScrollArea::vertical()
+            .max_height(200.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                Grid::new("user_card_messages")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for line in recent.iter().rev() {
+                            let data = line.msg.as_privmsg().map(|pm| pm.data).unwrap_or_default();
+                            ui.small(line.ts.as_str());
+                            ui.add(Label::new(data).wrap(true));
+                            ui.end_row();
+                        }
+                    });
+            });
+
+        action
+    }
+
+    fn format_date(created_at: &str) -> String {
+        static FORMAT: &[time::format_description::FormatItem<'static>] =
+            time::macros::format_description!("[year]-[month]-[day]");
+
+        time::OffsetDateTime::parse(created_at, &time::format_description::well_known::Rfc3339)
+            .ok()
+            .and_then(|dt| dt.format(FORMAT).ok())
+            .unwrap_or_else(|| created_at.to_string())
+    }
+}
@@ -21,28 +21,40 @@ impl<'a> UserList<'a> {
         // self.images.get(&kind.as_str()[..kind.as_str().len() - 1])
     }
 
-    pub fn display(self, ui: &mut egui::Ui) {
+    /// Returns the chatter that was clicked
+    pub fn display(self, ui: &mut egui::Ui) -> Option<String> {
         let width = ui
             .fonts()
             .glyph_width(&TextStyle::Body.resolve(ui.style()), ' ');
 
+        let mut clicked = None;
         ui.scope(|ui| {
             ui.spacing_mut().item_spacing.x = width;
             ui.style_mut().spacing.interact_size.y = width;
             let image_size = vec2(8.0, 8.0);
 
             ScrollArea::vertical().show(ui, |ui| {
-                for (kind, chatter) in &self.chatters.chatters {
+                for (kind, chatter) in self.chatters.iter() {
                     ui.horizontal(|ui| {
-                        if let Some(img) = self.get_image(*kind) {
+                        if let Some(img) = self.get_image(kind) {
                             img.show_size(ui, image_size);
                         } else {
                             ui.allocate_exact_size(image_size, Sense::hover());
                         }
-                        ui.add(Label::new(RichText::new(chatter).small()).wrap(false));
+                        if ui
+                            .add(
+                                Label::new(RichText::new(chatter).small())
+                                    .wrap(false)
+                                    .sense(Sense::click()),
+                            )
+                            .clicked()
+                        {
+                            clicked.replace(chatter.to_string());
+                        }
                     });
                 }
             });
         });
+        clicked
     }
 }
@@ -0,0 +1,133 @@
+use egui::{
+    Direction, Frame, Label, Layout, RichText, ScrollArea, SelectableLabel, SidePanel, TextEdit,
+    TopBottomPanel,
+};
+
+use crate::{
+    font_icon,
+    whispers::{WhisperLine, Whispers},
+};
+
+use super::Timestamp;
+
+pub struct WhisperView<'a> {
+    whispers: &'a mut Whispers,
+    show_timestamp: bool,
+}
+
+impl<'a> WhisperView<'a> {
+    pub fn new(whispers: &'a mut Whispers, show_timestamp: bool) -> Self {
+        Self {
+            whispers,
+            show_timestamp,
+        }
+    }
+
+    /// Returns who to whisper, and what
+    pub fn display(mut self, ui: &mut egui::Ui) -> Option<(String, String)> {
+        SidePanel::left("whisper_threads")
+            .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+            .show_inside(ui, |ui| self.display_threads(ui));
+
+        let active = self.whispers.active.clone()?;
+        self.whispers.mark_read(&active);
+
+        let mut reply = None;
+        TopBottomPanel::bottom("whisper_reply")
+            .resizable(false)
+            .frame(Frame::none().fill(ui.style().visuals.faint_bg_color))
+            .show_inside(ui, |ui| {
+                ui.with_layout(
+                    Layout::centered_and_justified(Direction::LeftToRight),
+                    |ui| {
+                        let resp = ui.add(
+                            TextEdit::singleline(&mut self.whispers.reply)
+                                .hint_text(format!("whisper {active}"))
+                                .frame(false)
+                                .lock_focus(true),
+                        );
+
+                        if resp.lost_focus()
+                            && ui.ctx().input().key_down(egui::Key::Enter)
+                            && !self.whispers.reply.trim().is_empty()
+                        {
+                            let data = std::mem::take(&mut self.whispers.reply);
+                            reply.replace((active.clone(), data));
+                        }
+                        // but don't steal it from the new thread box
+                        if ui.memory().focus().is_none() {
+                            resp.request_focus();
+                        }
+                    },
+                );
+            });
+
+        let thread = self.whispers.active_thread()?;
+        ScrollArea::vertical()
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in &thread.lines {
+                    ui.horizontal_wrapped(|ui| match line {
+                        WhisperLine::Message(at, msg) => {
+                            self.display_timestamp(ui, *at);
+                            ui.add(Label::new(RichText::new(&msg.from).strong()));
+                            ui.add(Label::new(&msg.data).wrap(true));
+                        }
+                        WhisperLine::Error(at, err) => {
+                            self.display_timestamp(ui, *at);
+                            ui.colored_label(ui.visuals().error_fg_color, err);
+                        }
+                    });
+                }
+            });
+
+        reply
+    }
+
+    fn display_threads(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let resp = ui.add(
+                TextEdit::singleline(&mut self.whispers.new_login)
+                    .hint_text("user")
+                    .desired_width(100.0),
+            );
+            let enter = resp.lost_focus() && ui.ctx().input().key_down(egui::Key::Enter);
+
+            let login = self.whispers.new_login.trim().to_string();
+            if (ui.small_button(font_icon::ADD).clicked() || enter) && !login.is_empty() {
+                self.whispers.open(&login);
+                self.whispers.new_login.clear();
+            }
+        });
+
+        ui.separator();
+
+        let mut open = None;
+        ScrollArea::vertical().show(ui, |ui| {
+            for thread in self.whispers.threads() {
+                let selected = self.whispers.active.as_deref() == Some(&*thread.login);
+                let text = match thread.unread {
+                    0 => RichText::new(&thread.login),
+                    n => RichText::new(format!("{} ({n})", thread.login)).strong(),
+                };
+                if ui.add(SelectableLabel::new(selected, text)).clicked() {
+                    open.replace(thread.login.clone());
+                }
+            }
+        });
+
+        if let Some(login) = open {
+            self.whispers.active.replace(login);
+        }
+    }
+
+    fn display_timestamp(&self, ui: &mut egui::Ui, at: i64) {
+        if !self.show_timestamp {
+            return;
+        }
+        if let Some(ts) = Timestamp::from_unix(at) {
+            ui.small(ts.as_str());
+        }
+    }
+}
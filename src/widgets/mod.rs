@@ -10,7 +10,8 @@ pub use settings::{ActiveSettingsView, SettingsView};
 pub mod state {
     pub use super::main::{ChatViewState, MainViewState};
     pub use super::settings::{
-        KeybindingsState, SettingsState, TwitchChannelsState, TwitchSettingsState,
+        FilterSettingsState, KeybindingsState, SettingsState, TwitchChannelsState,
+        TwitchSettingsState,
     };
     pub use super::start::StartState;
 }
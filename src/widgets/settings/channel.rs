@@ -3,7 +3,6 @@ use std::borrow::Cow;
 use egui::{
     vec2, Align, Frame, Key, Label, Layout, RichText, ScrollArea, TextEdit, TopBottomPanel,
 };
-use poll_promise::Promise;
 
 use crate::{
     fetch::ImageKind,
@@ -25,7 +24,7 @@ pub struct TwitchChannelsState {
 
 pub struct ChannelSettings<'a> {
     state: &'a mut TwitchChannelsState,
-    helix: &'a Promise<helix::Client>,
+    helix: Option<&'a helix::Client>,
     channels: &'a mut Vec<Channel>,
     images: &'a ImageCache,
     fetch: &'a mut FetchQueue<Image>,
@@ -35,7 +34,7 @@ impl<'a> ChannelSettings<'a> {
     pub fn new(
         state: &'a mut TwitchChannelsState,
         channels: &'a mut Vec<Channel>,
-        helix: &'a Promise<helix::Client>,
+        helix: Option<&'a helix::Client>,
         images: &'a ImageCache,
         fetch: &'a mut FetchQueue<Image>,
     ) -> Self {
@@ -55,12 +54,11 @@ impl<'a> ChannelSettings<'a> {
             .show_inside(ui, |ui| {
                 let mut resp = ui.add(
                     TextEdit::singleline(&mut self.state.buffer)
-                        .text_color(
-                            self.state
-                                .showing_error
-                                .then(|| ui.style().visuals.error_fg_color)
-                                .unwrap_or_else(|| ui.style().visuals.text_color()),
-                        )
+                        .text_color(if self.state.showing_error {
+                            ui.style().visuals.error_fg_color
+                        } else {
+                            ui.style().visuals.text_color()
+                        })
                         .frame(false)
                         .hint_text(Self::DEFAULT_CHANNEL_HINT)
                         .lock_focus(true),
@@ -109,13 +107,13 @@ impl<'a> ChannelSettings<'a> {
                                     }
                                 }
 
-                                ui.add(Label::new(
-                                    RichText::new(&channel.login).monospace().color(
-                                        duplicate
-                                            .then(|| ui.style().visuals.warn_fg_color)
-                                            .unwrap_or_else(|| ui.style().visuals.text_color()),
-                                    ),
-                                ))
+                                ui.add(Label::new(RichText::new(&channel.login).monospace().color(
+                                    if duplicate {
+                                        ui.style().visuals.warn_fg_color
+                                    } else {
+                                        ui.style().visuals.text_color()
+                                    },
+                                )))
                             })
                             .inner;
 
@@ -166,14 +164,18 @@ impl<'a> ChannelSettings<'a> {
             return;
         }
 
-        let helix = match self.helix.ready() {
+        let helix = match self.helix {
             Some(helix) => helix,
             None => return,
         };
 
-        let mut users = helix
-            .get_users([IdOrLogin::Login(&*channel)])
-            .expect("get users from twitch");
+        let mut users = match helix.get_users([IdOrLogin::Login(&*channel)]) {
+            Ok(users) => users,
+            Err(err) => {
+                self.report_error(format!("Cannot look up the channel: {err}"));
+                return;
+            }
+        };
 
         if users.is_empty() {
             let problem = channel.clone();
@@ -248,8 +250,11 @@ impl<'a> ChannelSettings<'a> {
     }
 
     fn update_tooltip(bool: bool, text: &str) -> String {
-        bool.then(|| format!("Disable {text}"))
-            .unwrap_or_else(|| format!("Enable {text}"))
+        if bool {
+            format!("Disable {text}")
+        } else {
+            format!("Enable {text}")
+        }
     }
 
     fn id() -> &'static str {
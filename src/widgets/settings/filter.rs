@@ -0,0 +1,131 @@
+use std::collections::BTreeSet;
+
+use egui::{Grid, RichText, TextEdit};
+
+use crate::{
+    font_icon::{ADD, REMOVE},
+    FilterPattern, MessageFilters,
+};
+
+#[derive(Default)]
+pub struct FilterSettingsState {
+    new_user: String,
+}
+
+pub struct FilterSettings<'a> {
+    state: &'a mut FilterSettingsState,
+    ignored_users: &'a mut BTreeSet<String>,
+    filters: &'a mut MessageFilters,
+}
+
+impl<'a> FilterSettings<'a> {
+    pub fn new(
+        state: &'a mut FilterSettingsState,
+        ignored_users: &'a mut BTreeSet<String>,
+        filters: &'a mut MessageFilters,
+    ) -> Self {
+        Self {
+            state,
+            ignored_users,
+            filters,
+        }
+    }
+
+    pub fn display(mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.filters.hide_commands,
+            "Hide commands (messages starting with !)",
+        );
+        ui.checkbox(
+            &mut self.filters.hide_replies_to_ignored,
+            "Hide replies to ignored users",
+        );
+
+        ui.separator();
+        self.display_ignored_users(ui);
+
+        ui.separator();
+        self.display_patterns(ui);
+    }
+
+    fn display_ignored_users(&mut self, ui: &mut egui::Ui) {
+        ui.label(RichText::new("Ignored users").strong());
+
+        ui.horizontal(|ui| {
+            let resp = ui.add(
+                TextEdit::singleline(&mut self.state.new_user)
+                    .hint_text("a login, e.g. nightbot")
+                    .desired_width(150.0),
+            );
+            let enter = resp.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+            let login = self.state.new_user.trim().to_lowercase();
+            if (ui
+                .add_enabled(!login.is_empty(), egui::Button::new(ADD).small())
+                .clicked()
+                || enter)
+                && !login.is_empty()
+            {
+                self.ignored_users.insert(login);
+                self.state.new_user.clear();
+            }
+        });
+
+        let mut remove = None;
+        for login in self.ignored_users.iter() {
+            ui.horizontal(|ui| {
+                if ui.small_button(REMOVE).on_hover_text("unignore").clicked() {
+                    remove.replace(login.clone());
+                }
+                ui.monospace(login);
+            });
+        }
+        if let Some(login) = remove {
+            self.ignored_users.remove(&login);
+        }
+    }
+
+    fn display_patterns(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Patterns").strong());
+            if ui
+                .small_button(ADD)
+                .on_hover_text("add a pattern")
+                .clicked()
+            {
+                self.filters.patterns.push(FilterPattern::default());
+            }
+        });
+
+        let mut remove = None;
+        Grid::new("filter_patterns")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, pattern) in self.filters.patterns.iter_mut().enumerate() {
+                    ui.vertical(|ui| {
+                        if ui
+                            .add(
+                                TextEdit::singleline(&mut pattern.pattern)
+                                    .hint_text("a regex over the message"),
+                            )
+                            .changed()
+                        {
+                            pattern.compile();
+                        }
+                        if let Some(err) = pattern.error() {
+                            ui.small(RichText::new(err).color(ui.visuals().error_fg_color));
+                        }
+                    });
+
+                    if ui.small_button(REMOVE).on_hover_text("remove").clicked() {
+                        remove.replace(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(i) = remove {
+            self.filters.patterns.remove(i);
+        }
+    }
+}
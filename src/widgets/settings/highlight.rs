@@ -0,0 +1,88 @@
+use egui::{ComboBox, DragValue, Grid, RichText, TextEdit};
+
+use crate::{
+    font_icon::{ADD, REMOVE},
+    HighlightMatch, HighlightRule, HighlightRules,
+};
+
+pub struct HighlightSettings<'a> {
+    rules: &'a mut HighlightRules,
+}
+
+impl<'a> HighlightSettings<'a> {
+    pub fn new(rules: &'a mut HighlightRules) -> Self {
+        Self { rules }
+    }
+
+    pub fn display(self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.small_button(ADD).on_hover_text("add a rule").clicked() {
+                self.rules.rules.push(HighlightRule::default());
+            }
+            ui.weak("mentions of your name are highlighted unless a rule matches");
+        });
+
+        ui.separator();
+
+        if self.rules.rules.is_empty() {
+            ui.weak("no highlight rules");
+            return;
+        }
+
+        let mut remove = None;
+        Grid::new("highlight_rules")
+            .num_columns(6)
+            .striped(true)
+            .show(ui, |ui| {
+                // the last column is the remove button
+                for label in ["Match", "Pattern", "Color", "Priority", "Mentions", ""] {
+                    ui.label(RichText::new(label).strong());
+                }
+                ui.end_row();
+
+                for (i, rule) in self.rules.rules.iter_mut().enumerate() {
+                    let mut changed = false;
+                    ComboBox::from_id_source(("highlight_kind", i))
+                        .width(70.0)
+                        .selected_text(rule.kind.display())
+                        .show_ui(ui, |ui| {
+                            for kind in HighlightMatch::ALL {
+                                changed |= ui
+                                    .selectable_value(&mut rule.kind, kind, kind.display())
+                                    .changed();
+                            }
+                        });
+
+                    ui.vertical(|ui| {
+                        changed |= ui
+                            .add(
+                                TextEdit::singleline(&mut rule.pattern).hint_text(rule.kind.hint()),
+                            )
+                            .changed();
+                        if let Some(err) = rule.error() {
+                            ui.small(RichText::new(err).color(ui.visuals().error_fg_color));
+                        }
+                    });
+
+                    ui.color_edit_button_srgb(&mut rule.color);
+                    ui.add(DragValue::new(&mut rule.priority))
+                        .on_hover_text("the highest priority rule wins");
+                    ui.checkbox(&mut rule.copy_to_mentions, "")
+                        .on_hover_text("copy matching lines to the mentions tab");
+
+                    if ui.small_button(REMOVE).on_hover_text("remove").clicked() {
+                        remove.replace(i);
+                    }
+                    ui.end_row();
+
+                    if changed {
+                        rule.compile();
+                    }
+                }
+            });
+
+        if let Some(i) = remove {
+            self.rules.rules.remove(i);
+        }
+    }
+}
@@ -4,11 +4,15 @@ use crate::state::{AppState, State};
 
 mod channel;
 mod display;
+mod filter;
+mod highlight;
 mod keybind;
 mod twitch;
 
 pub use channel::{ChannelSettings, TwitchChannelsState};
 pub use display::DisplaySettings;
+pub use filter::{FilterSettings, FilterSettingsState};
+pub use highlight::HighlightSettings;
 pub use keybind::{KeybindSettings, KeybindingsState};
 pub use twitch::{TwitchSettings, TwitchSettingsState};
 
@@ -19,6 +23,8 @@ pub enum ActiveSettingsView {
     KeyBindings,
     Twitch,
     Display,
+    Highlights,
+    Filters,
     None,
 }
 
@@ -52,6 +58,12 @@ impl<'a> SettingsView<'a> {
             );
             ui.selectable_value(&mut self.state.state.settings.active, Twitch, "Twitch");
             ui.selectable_value(&mut self.state.state.settings.active, Display, "Display");
+            ui.selectable_value(
+                &mut self.state.state.settings.active,
+                Highlights,
+                "Highlights",
+            );
+            ui.selectable_value(&mut self.state.state.settings.active, Filters, "Filters");
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                 ui.button("close").clicked()
@@ -69,6 +81,8 @@ impl<'a> SettingsView<'a> {
                 KeyBindings => self.display_keybindings(ui),
                 Twitch => self.display_twitch(ui),
                 Display => self.display_display(ui),
+                Highlights => self.display_highlights(ui),
+                Filters => self.display_filters(ui),
                 _ => {}
             });
 
@@ -76,10 +90,11 @@ impl<'a> SettingsView<'a> {
     }
 
     fn display_channels(self, ui: &mut egui::Ui) {
+        let helix = self.state.runtime.helix().cloned();
         ChannelSettings::new(
             &mut self.state.state.twitch_channels,
             &mut self.state.state.channels,
-            &self.state.runtime.helix,
+            helix.as_ref(),
             &self.state.state.images,
             &mut self.state.runtime.fetch,
         )
@@ -98,6 +113,7 @@ impl<'a> SettingsView<'a> {
         TwitchSettings::new(
             &mut self.state.state.config,
             &mut self.state.state.twitch_settings,
+            &mut self.state.runtime.tokens,
         )
         .display(ui)
     }
@@ -105,4 +121,17 @@ impl<'a> SettingsView<'a> {
     fn display_display(self, ui: &mut egui::Ui) {
         DisplaySettings::new(&mut self.state.state, &self.state.dark_image_mask).display(ui)
     }
+
+    fn display_highlights(self, ui: &mut egui::Ui) {
+        HighlightSettings::new(&mut self.state.state.highlights).display(ui)
+    }
+
+    fn display_filters(self, ui: &mut egui::Ui) {
+        FilterSettings::new(
+            &mut self.state.state.filter_settings,
+            &mut self.state.state.ignored_users,
+            &mut self.state.state.filters,
+        )
+        .display(ui)
+    }
 }
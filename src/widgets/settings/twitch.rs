@@ -1,10 +1,12 @@
 use std::{borrow::Cow, collections::HashMap};
 
-use egui::{Align, Grid, Label, Layout, RichText, TextEdit};
+use egui::{Align, Button, Grid, Label, Layout, RichText, Sense, TextEdit};
 
 pub struct TwitchSettingsState {
     show_password: HashMap<u64, bool>,
     seems_good: [bool; 4],
+    login: Option<Login>,
+    login_error: Option<String>,
 }
 
 impl Default for TwitchSettingsState {
@@ -12,6 +14,8 @@ impl Default for TwitchSettingsState {
         Self {
             show_password: Default::default(),
             seems_good: [true; 4],
+            login: None,
+            login_error: None,
         }
     }
 }
@@ -22,23 +26,37 @@ impl TwitchSettingsState {
     }
 }
 
-use crate::{state::State, EnvConfig};
+use crate::{
+    login::Login,
+    state::State,
+    token::{TokenInfo, TokenManager},
+    EnvConfig,
+};
 
 pub struct TwitchSettings<'a> {
     config: &'a mut EnvConfig,
     state: &'a mut TwitchSettingsState,
+    tokens: &'a mut TokenManager,
 }
 
 impl<'a> TwitchSettings<'a> {
-    pub fn new(config: &'a mut EnvConfig, state: &'a mut TwitchSettingsState) -> Self {
-        Self { config, state }
+    pub fn new(
+        config: &'a mut EnvConfig,
+        state: &'a mut TwitchSettingsState,
+        tokens: &'a mut TokenManager,
+    ) -> Self {
+        Self {
+            config,
+            state,
+            tokens,
+        }
     }
 
     fn id() -> &'static str {
         std::any::type_name::<Self>()
     }
 
-    pub fn display(self, ui: &mut egui::Ui) {
+    pub fn display(mut self, ui: &mut egui::Ui) {
         Grid::new(Self::id())
             .num_columns(2)
             .striped(true)
@@ -95,6 +113,164 @@ impl<'a> TwitchSettings<'a> {
                     ui.end_row()
                 }
             });
+
+        ui.separator();
+        self.display_login(ui);
+
+        ui.separator();
+        self.display_token_status(ui);
+    }
+
+    fn display_login(&mut self, ui: &mut egui::Ui) {
+        let login = match &mut self.state.login {
+            Some(login) => login,
+            None => {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !self.config.twitch_client_id.is_empty(),
+                            Button::new("Log in with Twitch"),
+                        )
+                        .on_hover_text_at_pointer("Requires a Client-Id")
+                        .clicked()
+                    {
+                        self.state.login_error.take();
+                        self.state.login.replace(Login::start(
+                            self.tokens.base(),
+                            &self.config.twitch_client_id,
+                            ui.ctx().clone(),
+                        ));
+                    }
+
+                    if let Some(err) = &self.state.login_error {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                    }
+                });
+                return;
+            }
+        };
+
+        if let Some(res) = login.poll() {
+            self.state.login.take();
+            match res {
+                Ok(logged_in) => {
+                    self.config.twitch_name = logged_in.login;
+                    self.config.twitch_oauth_token =
+                        format!("oauth:{}", logged_in.token.access_token);
+                    self.config.twitch_refresh_token =
+                        logged_in.token.refresh_token.unwrap_or_default();
+                    self.tokens.validate(&self.config.twitch_oauth_token);
+                }
+                Err(err) => {
+                    self.state.login_error.replace(err);
+                }
+            }
+            return;
+        }
+
+        let cancel = ui
+            .horizontal(|ui| {
+                ui.spinner();
+
+                match login.code() {
+                    Some(code) => {
+                        ui.label("Enter");
+                        if ui
+                            .add(
+                                Label::new(RichText::new(&code.user_code).monospace().strong())
+                                    .sense(Sense::click()),
+                            )
+                            .on_hover_text_at_pointer("Click to copy")
+                            .clicked()
+                        {
+                            ui.output().copied_text = code.user_code.clone();
+                        }
+                        ui.label("at");
+                        ui.hyperlink(&code.verification_uri);
+                    }
+                    None => {
+                        ui.label("Waiting for Twitch");
+                    }
+                }
+
+                ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                    ui.small_button("cancel").clicked()
+                })
+                .inner
+            })
+            .inner;
+
+        if cancel {
+            self.state.login.take();
+        }
+    }
+
+    fn display_token_status(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.monospace("Token status");
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if self.tokens.is_validating() {
+                    ui.spinner();
+                    return;
+                }
+
+                if ui
+                    .small_button(crate::font_icon::UNDO)
+                    .on_hover_text_at_pointer("Validate the token again")
+                    .clicked()
+                {
+                    self.tokens.validate(&self.config.twitch_oauth_token);
+                }
+
+                if self.tokens.is_stale(&self.config.twitch_oauth_token) {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "The token changed since it was validated",
+                    );
+                }
+            });
+        });
+
+        match self.tokens.status() {
+            Some(Ok(info)) => Self::display_token_info(info, ui),
+            Some(Err(err)) => {
+                ui.colored_label(ui.visuals().error_fg_color, err);
+            }
+            None => {}
+        }
+    }
+
+    fn display_token_info(info: &TokenInfo, ui: &mut egui::Ui) {
+        Grid::new("token_info")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.monospace("Login");
+                ui.label(&info.login);
+                ui.end_row();
+
+                ui.monospace("Scopes");
+                if info.scopes.is_empty() {
+                    ui.weak("none");
+                } else {
+                    ui.add(Label::new(info.scopes.join(", ")).wrap(true));
+                }
+                ui.end_row();
+
+                ui.monospace("Expires");
+                match info.expires_in() {
+                    Some(expires) if expires.is_zero() => {
+                        ui.colored_label(ui.visuals().error_fg_color, "expired");
+                    }
+                    Some(expires) => {
+                        ui.label(format!("in {}", crate::format_seconds(expires.as_secs())));
+                    }
+                    None => {
+                        ui.label("never");
+                    }
+                }
+                ui.end_row();
+            });
     }
 
     fn label_for_name(ui: &mut egui::Ui) {
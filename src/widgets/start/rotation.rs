@@ -41,7 +41,7 @@ impl StartRotation {
         }
 
         // reset it
-        let _ = std::mem::replace(self, Self::new());
+        *self = Self::new();
         repaint.request_repaint();
     }
 }